@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use monkey::lexer::lexer::Lexer;
+
+const CHUNK: &str = r#"/// computes things
+var total_{n} = func (a, b, c) {
+    // sum the inputs
+    return a + b * c - 0xFF / 1_000 % 7
+}
+var msg_{n} = "value: ${total_{n}(1, 2.5e3, 0b1010)} größe\t\u{2764}"
+if (total_{n}(1, 2, 3) >= 10 && !false) { print(msg_{n}) } else { print([1, 2, 3]) }
+"#;
+
+/// Builds a script of at least `size` bytes. With `one_line` set all
+/// newlines are replaced so the whole input sits on a single line.
+fn script(size: usize, one_line: bool) -> String {
+    let mut out = String::with_capacity(size + CHUNK.len());
+    let mut n = 0;
+    while out.len() < size {
+        // reuse a bounded set of names, like real generated code does
+        out.push_str(&CHUNK.replace("{n}", &(n % 1000).to_string()));
+        n += 1;
+    }
+    if one_line {
+        out = out
+            .replace("/// computes things", "/* computes things */")
+            .replace("// sum the inputs", "/* sum the inputs */")
+            .replace('\n', " ");
+    }
+    out
+}
+
+fn lex(input: String) -> (usize, Duration) {
+    let start = Instant::now();
+    let count = Lexer::new(input).count();
+    (count, start.elapsed())
+}
+
+fn main() {
+    for one_line in [false, true] {
+        println!("{}", if one_line { "single line input" } else { "multi-line input" });
+        let mut per_byte = Vec::new();
+        for mb in [1, 2, 4, 8] {
+            let input = script(mb * 1024 * 1024, one_line);
+            let bytes = input.len();
+            let (tokens, elapsed) = lex(input);
+            let ns = elapsed.as_nanos() as f64 / bytes as f64;
+            per_byte.push(ns);
+            println!("  {:>2} MB: {:>9} tokens in {:>8.2?} ({:.2} ns/byte)", mb, tokens, elapsed, ns);
+        }
+        // linear time keeps the cost per byte flat as the input grows
+        println!("  8 MB vs 1 MB cost per byte: {:.2}x", per_byte[3] / per_byte[0]);
+    }
+}
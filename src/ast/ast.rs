@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, rc::Rc};
 
 pub type BlockStmt = Vec<Statement>;
 
@@ -6,7 +6,7 @@ pub type Program = BlockStmt;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Ident {
-    pub literal: String,
+    pub literal: Rc<str>,
 }
 impl fmt::Display for Ident {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -16,15 +16,77 @@ impl fmt::Display for Ident {
 
 
 
+/// A function parameter, `name`, `name = default` or `...name`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Param {
+    pub ident: Ident,
+    pub default: Option<Expression>,
+    // collects the remaining positional arguments into an array
+    pub rest: bool,
+}
+
+impl Param {
+    pub fn new(ident: Ident) -> Self {
+        Param {
+            ident,
+            default: None,
+            rest: false,
+        }
+    }
+}
+
+/// `pattern if guard => body` in a `match`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: BlockStmt,
+}
+
+/// A call argument, optionally passed by name as in `f(b: 3)`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Arg {
+    pub name: Option<Ident>,
+    pub value: Expression,
+}
+
+/// The left side of a destructuring `var`, or what a `match` arm accepts.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    // `name`, binds the whole value
+    Ident(Ident),
+    // `_`, matches anything without binding it
+    Wildcard,
+    // `1`, `"a"` or `true`, matches an equal value
+    Literal(Literals),
+    // `1..5` or `1..=4`, stored with the end excluded
+    Range(i64, i64),
+    // `[a, b, ...rest]`, where the rest is a name or `_`
+    Array(Vec<Pattern>, Option<Box<Pattern>>),
+    // `{name, age: years}`, where `name` is short for `name: name`
+    Hash(Vec<(String, Pattern)>),
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Prefix {
     Minus, 
+    Bang,
     None
 }
 
 impl fmt::Display for Prefix {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "-")
+        match self {
+            Prefix::Minus => {
+                write!(f, "-")
+            }
+            Prefix::Bang => {
+                write!(f, "!")
+            }
+            Prefix::None => {
+                write!(f, "")
+            }
+        }
     }
 }
 
@@ -34,10 +96,17 @@ pub enum Infix {
     Minus,
     Star,
     Slash,
+    Percent,
     GT, 
     LT,
+    GTE,
+    LTE,
     EQ, 
     NotEQ,
+    And,
+    Or,
+    Range,
+    RangeInclusive,
 
     None
 }
@@ -56,7 +125,40 @@ impl fmt::Display for Infix {
             Infix::Slash => {
                 write!(f, "/")
             }
-            _ => {
+            Infix::Percent => {
+                write!(f, "%")
+            }
+            Infix::GT => {
+                write!(f, ">")
+            }
+            Infix::LT => {
+                write!(f, "<")
+            }
+            Infix::GTE => {
+                write!(f, ">=")
+            }
+            Infix::LTE => {
+                write!(f, "<=")
+            }
+            Infix::EQ => {
+                write!(f, "==")
+            }
+            Infix::NotEQ => {
+                write!(f, "!=")
+            }
+            Infix::And => {
+                write!(f, "&&")
+            }
+            Infix::Or => {
+                write!(f, "||")
+            }
+            Infix::Range => {
+                write!(f, "..")
+            }
+            Infix::RangeInclusive => {
+                write!(f, "..=")
+            }
+            Infix::None => {
                 write!(f, "")
             }
 
@@ -64,30 +166,45 @@ impl fmt::Display for Infix {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Ident(Ident),
     Literal(Literals),
     Prefix(Prefix, Box<Expression>),
     Infix(Infix, Box<Expression>, Box<Expression>),
+    // `if (a) { .. } else if (b) { .. } else { .. }`, one branch per
+    // condition and the optional else block
     If(
-        Box<Expression>,
-        BlockStmt,
+        Vec<(Expression, BlockStmt)>,
         Option<BlockStmt>,
     ),
     Function(
-        Vec<Ident>, 
+        Vec<Param>, 
         BlockStmt,
     ),
 
     FunctionCall(
-        Vec<Expression>,
+        Vec<Arg>,
+        Box<Expression>,
+    ),
+    // `receiver.name(args)`
+    MethodCall(
         Box<Expression>,
+        Ident,
+        Vec<Arg>,
     ),
     Index(
         Box<Expression>,
         Box<Expression>,
     ),
+    // `left[start:end]`, where both bounds are optional
+    Slice(
+        Box<Expression>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+    ),
+    Template(Vec<Expression>),
+    Match(Box<Expression>, Vec<MatchArm>),
 
     None,
 }
@@ -108,19 +225,34 @@ impl fmt::Display for Expression{
             Expression::Literal(l) => {
                 write!(f, "{}", l)
             }
-            Expression::If(cond, i, e) => {
+            Expression::Template(parts) => {
+                let str = parts
+                    .iter()
+                    .map(|part| match part {
+                        Expression::Literal(Literals::String(s)) => s.clone(),
+                        _ => format!("${{{}}}", part),
+                    })
+                    .collect::<String>();
+                write!(f, "\"{}\"", str)
+            }
+            Expression::If(branches, e) => {
                 let mut str = String::new();
-                str = str + &format!("If({})", *cond);
+                for (n, (cond, i)) in branches.iter().enumerate() {
+                    if n > 0 {
+                        str += "else";
+                    }
+                    str = str + &format!("If({})", cond);
 
-                str = str + &i
-                    .iter()
-                    .map(|exp| format!("{}", exp))
-                    .collect::<Vec<String>>()
-                    .join("\n")
-                    .to_string();
+                    str = str + &i
+                        .iter()
+                        .map(|exp| format!("{}", exp))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                        .to_string();
+                }
 
                 if e.is_some() {
-                    str = str + "else";
+                    str += "else";
                     str = str + &e
                         .clone()
                         .unwrap()
@@ -144,11 +276,19 @@ impl fmt::Display for Expression{
 }
 
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     Var(Ident, Expression),
+    VarDestructure(Pattern, Expression),
     Return(Expression),
     ExpressionStmt(Expression),
+    // `target = value`, or `target += value` and the like with the operator
+    Assign(Expression, Option<Infix>, Expression),
+    // the first field is the optional label, as in `outer: while (...) {}`
+    While(Option<Ident>, Expression, BlockStmt),
+    For(Option<Ident>, Ident, Expression, BlockStmt),
+    Break(Option<Ident>),
+    Continue(Option<Ident>),
 
     None,
 }
@@ -166,12 +306,15 @@ impl fmt::Display for Statement {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Literals {
     Int(i64),
+    Float(f64),
     Bool(bool),
     String(String),
-    Arr(Vec<Expression>)
+    Arr(Vec<Expression>),
+    Tuple(Vec<Expression>),
+    Hash(Vec<(Expression, Expression)>),
 }
 impl fmt::Display for Literals {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -179,6 +322,9 @@ impl fmt::Display for Literals {
             Literals::Int(i) => {
                 write!(f, "{}", i)
             }
+            Literals::Float(fl) => {
+                write!(f, "{}", fl)
+            }
             Literals::Bool(b) => {
                 write!(f, "{}", b)
             }
@@ -194,6 +340,26 @@ impl fmt::Display for Literals {
                     .to_string();
                 write!(f, "{}", str)
             }
+            Literals::Hash(entries) => {
+                let str = entries
+                    .iter()
+                    .map(|(key, val)| format!("{}: {}", key, val))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "{{{}}}", str)
+            }
+            Literals::Tuple(items) => {
+                let str = items
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                if items.len() == 1 {
+                    write!(f, "({},)", str)
+                } else {
+                    write!(f, "({})", str)
+                }
+            }
         }
     }
 }
@@ -201,8 +367,12 @@ impl fmt::Display for Literals {
 #[derive(PartialEq, PartialOrd, Debug)]
 pub enum Precedences {
     Lowest,
+    Pipe,
+    Or,
+    And,
     Equals,     
     LessGreater, 
+    Range,
     Sum,         
     Product,    
     Prefix,    
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use crate::object::hash::HashKey;
 use crate::object::object::Object;
 
 
@@ -7,6 +8,11 @@ pub fn new_builtin_functions() -> HashMap<String, Object> {
     let mut map = HashMap::new();
     map.insert("len".to_string(), Object::Builtin(1, len));
     map.insert("print".to_string(), Object::Builtin(1, print));
+    map.insert("range".to_string(), Object::Builtin(2, range));
+    map.insert("keys".to_string(), Object::Builtin(1, keys));
+    map.insert("values".to_string(), Object::Builtin(1, values));
+    map.insert("has_key".to_string(), Object::Builtin(2, has_key));
+    map.insert("delete".to_string(), Object::Builtin(2, delete));
 
     map
 }
@@ -14,11 +20,20 @@ pub fn new_builtin_functions() -> HashMap<String, Object> {
 fn len(args: Vec<Object>) -> Object {
     
     match &args[0] {
-        Object::Arr(arr) => {
+        Object::Arr(arr) | Object::Tuple(arr) => {
             Object::Int(arr.len() as i64)
         }
         Object::String(str) => {
-            Object::Int(str.len() as i64)
+            Object::Int(str.chars().count() as i64)
+        }
+        Object::Range(start, end) => {
+            match end.checked_sub(*start) {
+                Some(len) => Object::Int(len.max(0)),
+                None => Object::Error(format!("the length of {} is too large", args[0])),
+            }
+        }
+        Object::Hash(map) => {
+            Object::Int(map.len() as i64)
         }
         _ => {
             Object::Error(format!("{} Doesnt Have A Length Property", args[0]))
@@ -31,4 +46,65 @@ fn print(args: Vec<Object>) -> Object {
     Object::None
 }
 
+fn range(args: Vec<Object>) -> Object {
+    match (&args[0], &args[1]) {
+        (Object::Int(start), Object::Int(end)) => {
+            Object::Range(*start, *end)
+        }
+        _ => {
+            Object::Error(format!("range needs two Ints, got {} and {}", args[0], args[1]))
+        }
+    }
+}
+
+fn keys(args: Vec<Object>) -> Object {
+    match &args[0] {
+        Object::Hash(map) => {
+            Object::Arr(map.keys().map(HashKey::to_object).collect())
+        }
+        _ => {
+            Object::Error(format!("keys needs a Hash, got {}", args[0]))
+        }
+    }
+}
+
+fn values(args: Vec<Object>) -> Object {
+    match &args[0] {
+        Object::Hash(map) => {
+            Object::Arr(map.values().cloned().collect())
+        }
+        _ => {
+            Object::Error(format!("values needs a Hash, got {}", args[0]))
+        }
+    }
+}
+
+fn has_key(args: Vec<Object>) -> Object {
+    match &args[0] {
+        Object::Hash(map) => {
+            // a value that can't be a key is in no hash
+            Object::Bool(HashKey::from_object(&args[1]).is_some_and(|key| map.contains_key(&key)))
+        }
+        _ => {
+            Object::Error(format!("has_key needs a Hash, got {}", args[0]))
+        }
+    }
+}
 
+/// Returns a copy of the hash without the key.
+fn delete(args: Vec<Object>) -> Object {
+    match &args[0] {
+        Object::Hash(map) => {
+            let key = match args[1].hash_key() {
+                Ok(key) => key,
+                Err(err) => return err,
+            };
+            let mut map = map.clone();
+            map.remove(&key);
+            Object::Hash(map)
+        }
+        _ => {
+            Object::Error(format!("delete needs a Hash, got {}", args[0]))
+        }
+    }
+}
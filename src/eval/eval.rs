@@ -3,12 +3,16 @@ use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 
-use crate::ast::ast::{Statement, Program, Expression, Literals, Infix, Prefix, BlockStmt, Ident};
+use crate::ast::ast::{Statement, Program, Expression, Literals, Infix, Prefix, BlockStmt, Ident, Param, Arg, Pattern, MatchArm};
 use crate::object::env::Env;
+use crate::object::hash::{HashKey, OrderedMap};
 use crate::object::object::*;
 
 use super::builtin;
 
+// positional and named arguments of a call
+type CallArgs = (Vec<Object>, Vec<(Rc<str>, Object)>);
+
 pub struct Eval {
     env: Rc<RefCell<Env>>,
     builtin: HashMap<String, Object>
@@ -21,37 +25,42 @@ impl Eval {
             builtin: builtin::new_builtin_functions()
         }
     }
-    pub fn eval_program(&mut self, program: Program) {
+    /// Evaluates every statement and returns the value of the last one,
+    /// or the first error.
+    pub fn eval_program(&mut self, program: Program) -> Object {
+        let mut last = Object::None;
         for s in program {
             let eval = self.eval_stmt(s);
             match eval {
-                Object::None => {}
                 Object::Error(err) => {
                     println!("Error {}", err);
-                    break;
+                    return Object::Error(err);
+                }
+                Object::Return(obj) => {
+                    return *obj
                 }
                 _ => {
+                    last = eval;
                 }
             }
         }
+        last
     }
 
+    /// Evaluates a block to the value of its last statement. A `return`
+    /// or an error stops the block and is passed on as it is, so it also
+    /// leaves any enclosing blocks up to the function call.
     fn eval_block(&mut self, block: BlockStmt) -> Object{
+        let mut last = Object::None;
         for s in block { 
             let evaled = self.eval_stmt(s);
-            match evaled {
-                Object::Return(o) => {
-                    return *o
-                }
-                Object::Error(err) => {
-                    return Object::Error(err)
-                }
-                _ => {
-                }
+            if evaled.is_signal() {
+                return evaled
             }
+            last = evaled;
         }
 
-        Object::None
+        last
     }
 
     fn eval_stmt(&mut self, stmt: Statement) -> Object {
@@ -61,11 +70,110 @@ impl Eval {
             }
             Statement::Var(i, exp) => {
                 let val = self.eval_exp(exp);
+                if val.is_signal() {
+                    return val
+                }
                 self.env.borrow_mut().add_ident(val, i.literal);
                 Object::None
             }
+            Statement::VarDestructure(pattern, exp) => {
+                let val = self.eval_exp(exp);
+                if val.is_signal() {
+                    return val
+                }
+                let mut bindings = Vec::new();
+                if let Err(err) = Self::match_pattern(&pattern, &val, &mut bindings) {
+                    return Self::new_error(err)
+                }
+                for (name, val) in bindings {
+                    self.env.borrow_mut().add_ident(val, name);
+                }
+                Object::None
+            }
             Statement::Return(exp) => {
-                Object::Return(Box::new(self.eval_exp(exp)))
+                let val = self.eval_exp(exp);
+                if val.is_signal() {
+                    return val
+                }
+                Object::Return(Box::new(val))
+            }
+            Statement::Assign(target, op, exp) => {
+                let mut val = self.eval_exp(exp);
+                if val.is_signal() {
+                    return val
+                }
+                // the indexes are evaluated once, for reading and writing
+                let (name, path) = match self.eval_place(target) {
+                    Ok(place) => place,
+                    Err(err) => return err,
+                };
+                if let Some(op) = op {
+                    let mut current = self.eval_exp(Expression::Ident(name.clone()));
+                    for ind in &path {
+                        current = self.index(current, ind.clone());
+                    }
+                    val = self.apply_infix(op, current, val);
+                }
+                if val.is_signal() {
+                    return val
+                }
+                self.assign(name, &path, val)
+            }
+            Statement::While(label, cond, body) => {
+                loop {
+                    let cond = self.eval_exp(cond.clone());
+                    if cond.is_signal() {
+                        return cond
+                    }
+                    if !self.is_true(cond) {
+                        return Object::None
+                    }
+                    if let Some(obj) = self.eval_loop_body(&label, body.clone()) {
+                        return obj
+                    }
+                }
+            }
+            Statement::For(label, ident, iterable, body) => {
+                let items = match self.eval_exp(iterable) {
+                    Object::Arr(items) | Object::Tuple(items) => {
+                        items
+                    }
+                    Object::Hash(map) => {
+                        map.keys().map(|key| key.to_object()).collect()
+                    }
+                    Object::String(s) => {
+                        s.chars().map(|c| Object::String(c.to_string())).collect()
+                    }
+                    Object::Range(start, end) => {
+                        // ranges are counted through instead of collected
+                        for i in start..end {
+                            self.env.borrow_mut().add_ident(Object::Int(i), ident.literal.clone());
+                            if let Some(obj) = self.eval_loop_body(&label, body.clone()) {
+                                return obj
+                            }
+                        }
+                        return Object::None
+                    }
+                    obj if obj.is_signal() => {
+                        return obj
+                    }
+                    obj => {
+                        return Self::new_error(format!("{} is not iterable", obj))
+                    }
+                };
+                for item in items {
+                    self.env.borrow_mut().add_ident(item, ident.literal.clone());
+                    if let Some(obj) = self.eval_loop_body(&label, body.clone()) {
+                        return obj
+                    }
+                }
+                Object::None
+            }
+            Statement::Break(label) => {
+                Object::Break(label.map(|l| l.literal))
+            }
+            Statement::Continue(label) => {
+                Object::Continue(label.map(|l| l.literal))
             }
             _=> {
                 Object::None
@@ -80,53 +188,68 @@ impl Eval {
             }
             Expression::FunctionCall(args, exp) => {
                 let function = self.eval_exp(*exp);
-            
-                let mut a = Vec::new();
-
-                for arg in args {
-                    let e = self.eval_exp(arg);
-                    match e {
-                        Object::None => {}
-                        _ => {
-                            a.push(e);
-                        }
-                    }
+                if function.is_signal() {
+                    return function
                 }
+                let (a, named) = match self.eval_args(args) {
+                    Ok(args) => args,
+                    Err(err) => return err,
+                };
 
-                self.apply_function(function, a)
+                self.apply_function(function, a, named)
             }
-            Expression::If(cond, if_block, else_block) => {
-                let cond = self.eval_exp(*cond);
-                if self.is_true(cond) {
-                    return self.eval_block(if_block)
-                } else if else_block.is_some() {
-                    return self.eval_block(else_block.unwrap())
+            Expression::MethodCall(receiver, name, args) => {
+                let receiver = self.eval_exp(*receiver);
+                if receiver.is_signal() {
+                    return receiver
+                }
+                // `x.f(args)` is `f(x, args)`, with `f` looked up like any name
+                let function = match self.lookup(&name.literal) {
+                    Some(function) => function,
+                    None => return Self::new_error(format!("no function `{}` to call on {}", name, receiver)),
+                };
+                let (mut a, named) = match self.eval_args(args) {
+                    Ok(args) => args,
+                    Err(err) => return err,
+                };
+                a.insert(0, receiver);
+
+                self.apply_function(function, a, named)
+            }
+            Expression::If(branches, else_block) => {
+                for (cond, block) in branches {
+                    let cond = self.eval_exp(cond);
+                    if cond.is_signal() {
+                        return cond
+                    }
+                    if self.is_true(cond) {
+                        return self.eval_block(block)
+                    }
+                }
+                if let Some(else_block) = else_block {
+                    return self.eval_block(else_block)
                 }
 
                 Object::None
             }
             Expression::Ident(i) => {
-                match self.env.borrow().read_ident(&i.literal) {
+                match self.lookup(&i.literal) {
                     Some(val) => {
-                        return val.clone()
+                        val
                     }
-                    None => {}
-
-                }
-                match self.builtin.get(&i.literal) {
-                    Some(val) => {
-                        return val.clone()
+                    None => {
+                        Self::new_error(format!("{} not found in the current scope", &i.literal))
                     }
-                    None => {}
                 }
-
-                Self::new_error(format!("{} not found in the current scope", &i.literal))
             }
             Expression::Literal(l) => {
                 match l {
                     Literals::Int(i) => {
                         Object::Int(i)
                     }
+                    Literals::Float(f) => {
+                        Object::Float(f)
+                    }
                     Literals::Bool(b) => {
                         Object::Bool(b)
                     }
@@ -134,34 +257,96 @@ impl Eval {
                         Object::String(s)
                     }
                     Literals::Arr(arr) => {
-                        let a = arr
-                            .iter()
-                            .map(|exp| self.eval_exp(exp.clone()))
-                            .collect();
-
-                        Object::Arr(a)
+                        match self.eval_list(arr) {
+                            Ok(a) => Object::Arr(a),
+                            Err(err) => err,
+                        }
+                    }
+                    Literals::Tuple(items) => {
+                        match self.eval_list(items) {
+                            Ok(t) => Object::Tuple(t),
+                            Err(err) => err,
+                        }
+                    }
+                    Literals::Hash(entries) => {
+                        let mut map = OrderedMap::new();
+                        for (key, val) in entries {
+                            let key = self.eval_exp(key);
+                            if key.is_signal() {
+                                return key
+                            }
+                            let key = match key.hash_key() {
+                                Ok(key) => key,
+                                Err(err) => return err,
+                            };
+                            match self.eval_exp(val) {
+                                val if val.is_signal() => {
+                                    return val
+                                }
+                                val => {
+                                    map.insert(key, val);
+                                }
+                            }
+                        }
+                        Object::Hash(map)
                     }
                 }
 
             }
             Expression::Index(left, ind) => {
                 let left = self.eval_exp(*left);
-                let arr = match left {
-                    Object::Arr(arr) => {
-                        arr
-                    }
-                    _ => {
-                        return Self::new_error("Indexed Object is not of type Array")
-                    }
-                };
-                match self.eval_exp(*ind){
-                    Object::Int(i) => {
-                        arr[i as usize].clone()
+                if left.is_signal() {
+                    return left
+                }
+                let ind = self.eval_exp(*ind);
+                if ind.is_signal() {
+                    return ind
+                }
+                self.index(left, ind)
+            }
+            Expression::Slice(left, start, end) => {
+                let left = self.eval_exp(*left);
+                if left.is_signal() {
+                    return left
+                }
+                let mut bounds = [None, None];
+                for (bound, exp) in bounds.iter_mut().zip([start, end]) {
+                    match exp.map(|exp| self.eval_exp(*exp)) {
+                        Some(Object::Int(i)) => {
+                            *bound = Some(i);
+                        }
+                        Some(obj) if obj.is_signal() => {
+                            return obj
+                        }
+                        Some(obj) => {
+                            return Self::new_error(format!("slice bounds must be Ints, got {}", obj))
+                        }
+                        None => {}
                     }
-                    _ => {
-                        Self::new_error("Need String to Index an Array")
+                }
+                self.slice(left, bounds[0], bounds[1])
+            }
+            Expression::Template(parts) => {
+                let mut str = String::new();
+                for part in parts {
+                    match self.eval_exp(part) {
+                        obj if obj.is_signal() => {
+                            return obj
+                        }
+                        obj => {
+                            str += &obj.to_string();
+                        }
                     }
                 }
+
+                Object::String(str)
+            }
+            Expression::Match(subject, arms) => {
+                let subject = self.eval_exp(*subject);
+                if subject.is_signal() {
+                    return subject
+                }
+                self.eval_match(subject, arms)
             }
             Expression::Prefix(p, right) => {
                 self.eval_prefix(p, *right)
@@ -175,16 +360,407 @@ impl Eval {
         }
     }
 
+    /// Evaluates an assignment target into the name it changes and the
+    /// indexes leading to the changed element.
+    fn eval_place(&mut self, target: Expression) -> Result<(Ident, Vec<Object>), Object> {
+        match target {
+            Expression::Ident(i) => {
+                Ok((i, Vec::new()))
+            }
+            Expression::Index(left, ind) => {
+                let (name, mut path) = self.eval_place(*left)?;
+                let ind = self.eval_exp(*ind);
+                if ind.is_signal() {
+                    return Err(ind)
+                }
+                path.push(ind);
+                Ok((name, path))
+            }
+            _ => {
+                Err(Self::new_error("only names and indexes can be assigned to"))
+            }
+        }
+    }
+
+    /// Sets the element at `path` inside the value of `name`.
+    fn assign(&mut self, name: Ident, path: &[Object], val: Object) -> Object {
+        let val = if path.is_empty() {
+            val
+        } else {
+            let container = match self.env.borrow().read_ident(&name.literal) {
+                Some(container) => container,
+                None => return Self::new_error(format!("cannot assign to `{}`, it is not declared", name.literal)),
+            };
+            match self.store(container, path, val) {
+                Ok(updated) => updated,
+                Err(err) => return err,
+            }
+        };
+        if !self.env.borrow_mut().assign(&name.literal, val) {
+            return Self::new_error(format!("cannot assign to `{}`, it is not declared", name.literal))
+        }
+        Object::None
+    }
+
+    /// Returns `container` with the element at `path` replaced by `val`.
+    fn store(&mut self, container: Object, path: &[Object], val: Object) -> Result<Object, Object> {
+        let (ind, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Ok(val),
+        };
+        let val = if rest.is_empty() {
+            val
+        } else {
+            let inner = self.index(container.clone(), ind.clone());
+            if let Object::Error(err) = inner {
+                return Err(Object::Error(err))
+            }
+            self.store(inner, rest, val)?
+        };
+
+        match (container, ind) {
+            (Object::Arr(mut items), Object::Int(i)) => {
+                match Self::position(*i, items.len()) {
+                    Some(i) => {
+                        items[i] = val;
+                        Ok(Object::Arr(items))
+                    }
+                    None => {
+                        Err(Self::new_error(format!("index {} is out of range for length {}", i, items.len())))
+                    }
+                }
+            }
+            (Object::Hash(mut map), key) => {
+                let key = key.hash_key()?;
+                map.insert(key, val);
+                Ok(Object::Hash(map))
+            }
+            (container, ind) => {
+                Err(Self::new_error(format!("cannot assign to index {} of {}", ind, container)))
+            }
+        }
+    }
+
+    /// Runs one iteration of a loop body. Returns the value the loop should
+    /// stop with, if it should stop.
+    fn eval_loop_body(&mut self, label: &Option<Ident>, body: BlockStmt) -> Option<Object> {
+        let targets = |l: &Option<Rc<str>>| match (l, label) {
+            (None, _) => true,
+            (Some(l), Some(label)) => *l == label.literal,
+            _ => false,
+        };
+        match self.eval_block(body) {
+            Object::Break(l) if targets(&l) => {
+                Some(Object::None)
+            }
+            Object::Continue(l) if targets(&l) => {
+                None
+            }
+            obj @ (Object::Return(_) | Object::Break(_) | Object::Continue(_) | Object::Error(_)) => {
+                Some(obj)
+            }
+            _ => {
+                None
+            }
+        }
+    }
+
+    fn index(&mut self, left: Object, ind: Object) -> Object {
+        match (left, ind) {
+            (Object::Error(err), _) | (_, Object::Error(err)) => {
+                Object::Error(err)
+            }
+            (Object::Hash(map), key) => {
+                let key = match key.hash_key() {
+                    Ok(key) => key,
+                    Err(err) => return err,
+                };
+                match map.get(&key) {
+                    Some(val) => val.clone(),
+                    None => Self::new_error(format!("key {} not found in hash", key)),
+                }
+            }
+            (Object::Arr(items) | Object::Tuple(items), Object::Int(i)) => {
+                match Self::position(i, items.len()) {
+                    Some(i) => items[i].clone(),
+                    None => Self::new_error(format!("index {} is out of range for length {}", i, items.len())),
+                }
+            }
+            (Object::String(s), Object::Int(i)) => {
+                let chars: Vec<char> = s.chars().collect();
+                match Self::position(i, chars.len()) {
+                    Some(i) => Object::String(chars[i].to_string()),
+                    None => Self::new_error(format!("index {} is out of range for length {}", i, chars.len())),
+                }
+            }
+            (left, Object::Range(start, end)) => {
+                self.slice(left, Some(start), Some(end))
+            }
+            (left @ (Object::Arr(_) | Object::Tuple(_) | Object::String(_)), ind) => {
+                Self::new_error(format!("{} can't be indexed with {}", left, ind))
+            }
+            (left, _) => {
+                Self::new_error(format!("{} can't be indexed", left))
+            }
+        }
+    }
+
+    /// Takes the elements from `start` up to `end`, by default the first and
+    /// the last one.
+    fn slice(&mut self, obj: Object, start: Option<i64>, end: Option<i64>) -> Object {
+        let len = match &obj {
+            Object::Arr(items) | Object::Tuple(items) => items.len(),
+            Object::String(s) => s.chars().count(),
+            Object::Error(_) => return obj,
+            _ => return Self::new_error(format!("{} can't be sliced", obj)),
+        };
+        // unlike an index, a bound may be right after the last element
+        let bound = |i: i64| {
+            let i = if i < 0 { i + len as i64 } else { i };
+            usize::try_from(i).ok().filter(|i| *i <= len)
+        };
+        let bounds = (start.map_or(Some(0), bound), end.map_or(Some(len), bound));
+        let (start, end) = match bounds {
+            (Some(start), Some(end)) if start <= end => (start, end),
+            _ => {
+                let show = |i: Option<i64>| i.map(|i| i.to_string()).unwrap_or_default();
+                return Self::new_error(format!("slice {}:{} is out of range for length {}", show(start), show(end), len))
+            }
+        };
+
+        match obj {
+            Object::Arr(items) => Object::Arr(items[start..end].to_vec()),
+            Object::Tuple(items) => Object::Tuple(items[start..end].to_vec()),
+            Object::String(s) => Object::String(s.chars().skip(start).take(end - start).collect()),
+            _ => Object::None,
+        }
+    }
+
+    /// Turns an index that may count from the end, like `-1`, into a
+    /// position below `len`.
+    fn position(i: i64, len: usize) -> Option<usize> {
+        let i = if i < 0 { i + len as i64 } else { i };
+        usize::try_from(i).ok().filter(|i| *i < len)
+    }
+
+    /// Runs the body of the first arm whose pattern matches and whose guard
+    /// holds. The names an arm binds are only visible in that arm.
+    fn eval_match(&mut self, subject: Object, arms: Vec<MatchArm>) -> Object {
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if Self::match_pattern(&arm.pattern, &subject, &mut bindings).is_err() {
+                continue;
+            }
+
+            let current_env = self.env.clone();
+            let mut arm_env = Env::new_with_outer(current_env.clone());
+            for (name, val) in bindings {
+                arm_env.add_ident(val, name);
+            }
+            self.env = Rc::new(RefCell::new(arm_env));
+
+            let guard = match arm.guard {
+                Some(guard) => self.eval_exp(guard),
+                None => Object::Bool(true),
+            };
+            let obj = if guard.is_signal() {
+                Some(guard)
+            } else if self.is_true(guard) {
+                Some(self.eval_block(arm.body))
+            } else {
+                None
+            };
+
+            self.env = current_env;
+            if let Some(obj) = obj {
+                return obj
+            }
+        }
+
+        Self::new_error(format!("no match arm matches {}", subject))
+    }
+
+    /// Checks that `val` has the shape of `pattern` and collects the values
+    /// it binds. The error tells where the shape differs.
+    fn match_pattern(pattern: &Pattern, val: &Object, bindings: &mut Vec<(Rc<str>, Object)>) -> Result<(), String> {
+        match (pattern, val) {
+            (Pattern::Wildcard, _) => {
+                Ok(())
+            }
+            (Pattern::Ident(ident), val) => {
+                bindings.push((ident.literal.clone(), val.clone()));
+                Ok(())
+            }
+            (Pattern::Literal(literal), val) => {
+                let expected = Object::from_literal(literal);
+                if !expected.equals(val) {
+                    return Err(format!("expected {}, found {}", expected, val))
+                }
+                Ok(())
+            }
+            (Pattern::Range(start, end), Object::Int(i)) if start <= i && i < end => {
+                Ok(())
+            }
+            (Pattern::Range(start, end), val) => {
+                Err(format!("expected a value in {}..{}, found {}", start, end, val))
+            }
+            (Pattern::Array(patterns, rest), Object::Arr(items) | Object::Tuple(items)) => {
+                if items.len() < patterns.len() || (rest.is_none() && items.len() > patterns.len()) {
+                    let at_least = if rest.is_some() { "at least " } else { "" };
+                    return Err(format!("expected {}{} elements, found {} in {}", at_least, patterns.len(), items.len(), val))
+                }
+                for (pattern, item) in patterns.iter().zip(items) {
+                    Self::match_pattern(pattern, item, bindings)?;
+                }
+                if let Some(rest) = rest {
+                    let remaining = items[patterns.len()..].to_vec();
+                    let remaining = match val {
+                        Object::Tuple(_) => Object::Tuple(remaining),
+                        _ => Object::Arr(remaining),
+                    };
+                    Self::match_pattern(rest, &remaining, bindings)?;
+                }
+                Ok(())
+            }
+            (Pattern::Hash(entries), Object::Hash(map)) => {
+                for (key, pattern) in entries {
+                    match map.get(&HashKey::String(key.clone())) {
+                        Some(item) => {
+                            Self::match_pattern(pattern, item, bindings)?;
+                        }
+                        None => {
+                            return Err(format!("key {} not found in {}", key, val))
+                        }
+                    }
+                }
+                Ok(())
+            }
+            (Pattern::Array(..), _) => {
+                Err(format!("expected an array, found {}", val))
+            }
+            (Pattern::Hash(_), _) => {
+                Err(format!("expected a hash, found {}", val))
+            }
+        }
+    }
+
+    /// Finds a name in the scopes, and then in the builtins.
+    fn lookup(&self, name: &str) -> Option<Object> {
+        match self.env.borrow().read_ident(name) {
+            Some(val) => {
+                return Some(val)
+            }
+            None => {}
+        }
+
+        self.builtin.get(name).cloned()
+    }
+
+    /// Evaluates call arguments into positional and named ones.
+    fn eval_args(&mut self, args: Vec<Arg>) -> Result<CallArgs, Object> {
+        let mut a = Vec::new();
+        let mut named = Vec::new();
+
+        for arg in args {
+            let e = self.eval_exp(arg.value);
+            match (e, arg.name) {
+                (e, _) if e.is_signal() => {
+                    return Err(e)
+                }
+                (e, Some(name)) => {
+                    named.push((name.literal, e));
+                }
+                (e, None) => {
+                    a.push(e);
+                }
+            }
+        }
+
+        Ok((a, named))
+    }
+
+    /// Evaluates each expression, stopping at the first error or other signal.
+    fn eval_list(&mut self, exps: Vec<Expression>) -> Result<Vec<Object>, Object> {
+        let mut objs = Vec::new();
+        for exp in exps {
+            match self.eval_exp(exp) {
+                obj if obj.is_signal() => {
+                    return Err(obj)
+                }
+                obj => {
+                    objs.push(obj);
+                }
+            }
+        }
+
+        Ok(objs)
+    }
+
     fn eval_infix(&mut self, o: Infix, left: Expression,  right: Expression) -> Object {
         let left = self.eval_exp(left);
+        if left.is_signal() {
+            return left
+        }
+        match o {
+            Infix::And | Infix::Or => {
+                return self.eval_logical(o, left, right)
+            }
+            _ => {}
+        }
         let right = self.eval_exp(right);
+        if right.is_signal() {
+            return right
+        }
+        self.apply_infix(o, left, right)
+    }
+
+    fn eval_logical(&mut self, o: Infix, left: Object, right: Expression) -> Object {
+        let left = self.is_true(left);
+        // the right side is only evaluated when it decides the result
+        if (o == Infix::And && !left) || (o == Infix::Or && left) {
+            return Object::Bool(left)
+        }
+
+        match self.eval_exp(right) {
+            right if right.is_signal() => {
+                right
+            }
+            right => {
+                Object::Bool(self.is_true(right))
+            }
+        }
+    }
+
+    fn apply_infix(&mut self, o: Infix, left: Object, right: Object) -> Object {
         match (left, right) {
+            (Object::Error(err), _) | (_, Object::Error(err)) => {
+                return Object::Error(err)
+            }
             (Object::Int(l), Object::Int(r)) => {
                 return self.calculate_int(o, l, r);
             }
+            (Object::Float(l), Object::Float(r)) => {
+                return self.calculate_float(o, l, r);
+            }
+            (Object::Int(l), Object::Float(r)) => {
+                return self.calculate_float(o, l as f64, r);
+            }
+            (Object::Float(l), Object::Int(r)) => {
+                return self.calculate_float(o, l, r as f64);
+            }
             (Object::String(l), Object::String(r)) => {
                 return self.calculate_string(o, l, r)
             }
+            (Object::Bool(l), Object::Bool(r)) => {
+                return self.bool_calculation(o, l, r)
+            }
+            (l @ (Object::Arr(_) | Object::Tuple(_) | Object::Hash(_) | Object::Range(..)), r) => {
+                return match o {
+                    Infix::EQ => Object::Bool(l.equals(&r)),
+                    Infix::NotEQ => Object::Bool(!l.equals(&r)),
+                    _ => Self::new_error(format!("{} is not a valid Infix Operator for {} {} {}", o, l, o, r)),
+                }
+            }
             _ => {}
         }
         return Object::None
@@ -193,8 +769,17 @@ impl Eval {
     fn eval_prefix(&mut self, o: Prefix, right: Expression) -> Object {
         let right = self.eval_exp(right);
         return match (o, right) {
+            (_, right) if right.is_signal() => {
+                right
+            }
             (Prefix::Minus, Object::Int(i)) => {
-                Object::Int(-1 * i)
+                Object::Int(-i)
+            }
+            (Prefix::Minus, Object::Float(f)) => {
+                Object::Float(-f)
+            }
+            (Prefix::Bang, obj) => {
+                Object::Bool(!self.is_true(obj))
             }
             _ => {
                 Object::None
@@ -212,26 +797,38 @@ impl Eval {
         self.bool_calculation(operator, left, right)
     }
 
-    fn apply_function(&mut self, function: Object, args: Vec<Object>) -> Object {
+    fn apply_function(&mut self, function: Object, args: Vec<Object>, named: Vec<(Rc<str>, Object)>) -> Object {
         match function {
-            Object::Function(i, block, env) => {
+            Object::Function(params, block, env) => {
 
                 let current_env = self.env.clone();
-                if i.len() != args.len() {
-                    return Self::new_error(format!("Need {} Arguments got {}", i.len(), args.len()))
-                }
 
-                let extended_env = self.extend_function_env(args, i, env);
-
-                self.env = Rc::new(RefCell::new(extended_env));
+                // defaults are evaluated in the new scope, so they can use
+                // the parameters before them
+                self.env = Rc::new(RefCell::new(Env::new_with_outer(env)));
+                if let Err(err) = self.bind_args(params, args, named) {
+                    self.env = current_env;
+                    return err
+                }
 
                 let obj = self.eval_block(block);
 
                 self.env = current_env;
 
-                obj
+                // without a `return` the value of the body is returned
+                match obj {
+                    Object::Return(obj) => {
+                        *obj
+                    }
+                    obj => {
+                        obj
+                    }
+                }
             }
             Object::Builtin(num, func) => {
+                if !named.is_empty() {
+                    return Self::new_error("builtin functions don't take named arguments")
+                }
                 if args.len() as i64 != num {
                     return Self::new_error(format!("Got {} Arguments but Want {}", args.len(), num))
                 }
@@ -244,26 +841,59 @@ impl Eval {
 
         }
     }
-    fn extend_function_env(&mut self, args: Vec<Object>, idents: Vec<Ident>, env: Rc<RefCell<Env>>) -> Env {
-        let mut new_env = Env::new_with_outer(env);
+    /// Binds the arguments of a call to the parameters in the current scope.
+    /// Positional arguments fill the parameters in order, extra ones go to
+    /// the rest parameter, then named ones fill the parameters left over.
+    fn bind_args(&mut self, params: Vec<Param>, args: Vec<Object>, named: Vec<(Rc<str>, Object)>) -> Result<(), Object> {
+        let positional = params.iter().filter(|param| !param.rest).count();
+        let has_rest = params.len() > positional;
+        if args.len() > positional && !has_rest {
+            return Err(Self::new_error(format!("Need at most {} Arguments got {}", positional, args.len())))
+        }
+
+        let mut args = args.into_iter();
+        let mut values: Vec<Option<Object>> = args.by_ref().take(positional).map(Some).collect();
+        values.resize(positional, None);
+        let mut rest: Vec<Object> = args.collect();
 
-        for i in 0.. idents.len() {
-            new_env.add_ident(args[i].clone(), idents[i].literal.clone());
+        for (name, val) in named {
+            let i = match params.iter().position(|param| !param.rest && param.ident.literal == name) {
+                Some(i) => i,
+                None => return Err(Self::new_error(format!("unknown argument `{}`", name))),
+            };
+            if values[i].is_some() {
+                return Err(Self::new_error(format!("argument `{}` is given twice", name)))
+            }
+            values[i] = Some(val);
+        }
+
+        let mut values = values.into_iter();
+        for param in params {
+            let val = if param.rest {
+                Object::Arr(std::mem::take(&mut rest))
+            } else {
+                match (values.next().flatten(), param.default) {
+                    (Some(val), _) => val,
+                    (None, Some(default)) => {
+                        match self.eval_exp(default) {
+                            val if val.is_signal() => return Err(val),
+                            val => val,
+                        }
+                    }
+                    (None, None) => {
+                        return Err(Self::new_error(format!("missing argument `{}`", param.ident)))
+                    }
+                }
+            };
+            self.env.borrow_mut().add_ident(val, param.ident.literal);
         }
 
-        new_env
+        Ok(())
     }
 
 
     fn is_true(&mut self, cond: Object) -> bool{
-        match cond {
-            Object::None | Object::Bool(false) => {
-                false
-            }
-            _ => {
-                true
-            }
-        }
+        !matches!(cond, Object::None | Object::Bool(false))
     }
 
     fn calculate_int(&mut self, o: Infix, n1: i64, n2: i64) -> Object {
@@ -283,6 +913,24 @@ impl Eval {
             Infix::Slash => {
                 return Object::Float(n1 as f64/ n2 as f64)
             }
+
+            Infix::Percent => {
+                if n2 == 0 {
+                    return Self::new_error(format!("{} % {} is a modulo by zero", n1, n2))
+                }
+                return Object::Int(n1.wrapping_rem(n2))
+            }
+
+            Infix::Range => {
+                return Object::Range(n1, n2)
+            }
+
+            Infix::RangeInclusive => {
+                return match n2.checked_add(1) {
+                    Some(end) => Object::Range(n1, end),
+                    None => Self::new_error(format!("{}..={} is too large", n1, n2)),
+                }
+            }
             _ => {
                 return self.bool_calculation(o, n1, n2)
             }
@@ -290,6 +938,29 @@ impl Eval {
         }
     }
 
+    fn calculate_float(&mut self, o: Infix, n1: f64, n2: f64) -> Object {
+        match o {
+            Infix::Plus => {
+                Object::Float(n1 + n2)
+            }
+            Infix::Minus => {
+                Object::Float(n1 - n2)
+            }
+            Infix::Star => {
+                Object::Float(n1 * n2)
+            }
+            Infix::Slash => {
+                Object::Float(n1 / n2)
+            }
+            Infix::Percent => {
+                Object::Float(n1 % n2)
+            }
+            _ => {
+                self.bool_calculation(o, n1, n2)
+            }
+        }
+    }
+
     fn bool_calculation<T: PartialEq + PartialOrd + fmt::Display>(&mut self, o: Infix, v1:T, v2:T) -> Object {
         match o {
             Infix::LT => {
@@ -298,6 +969,12 @@ impl Eval {
             Infix::GT => {
                 Object::Bool(v1 > v2)
             }
+            Infix::LTE => {
+                Object::Bool(v1 <= v2)
+            }
+            Infix::GTE => {
+                Object::Bool(v1 >= v2)
+            }
             Infix::EQ => {
                 Object::Bool(v1 == v2)
             }
@@ -1,10 +1,24 @@
-use crate::token::token::Token;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use unicode_xid::UnicodeXID;
+
+use crate::token::token::{Span, Token, TokenKind};
+
+const BOM: &[u8] = "\u{FEFF}".as_bytes();
 
 pub struct Lexer {
     pos: usize, 
     next_pos: usize,
     ch: u8, 
     input: Vec<u8>,
+    line: usize,
+    column: usize,
+    trivia: bool,
+    // brace depth inside each open `${ ... }` of a template string
+    templates: Vec<usize>,
+    // every identifier is allocated once and shared by its tokens
+    symbols: HashSet<Rc<str>>,
 }
 
 impl Lexer {
@@ -15,6 +29,11 @@ impl Lexer {
             next_pos: 0,
             ch: 0, 
             input: input.into_bytes(),
+            line: 1,
+            column: 0,
+            trivia: false,
+            templates: Vec::new(),
+            symbols: HashSet::new(),
         };
         lexer.read_char();
 
@@ -22,8 +41,26 @@ impl Lexer {
 
     }
 
+    /// Creates a lexer that also yields whitespace and comments as
+    /// trivia tokens, so the spans of all tokens cover the whole input.
+    pub fn new_lossless(input: String) -> Lexer {
+        let mut lexer = Lexer::new(input);
+        lexer.trivia = true;
+
+        lexer
+    }
+
     pub fn read_char(&mut self) {
         if self.next_pos < self.input.len() {
+            // a lone `\r` also ends a line, `\r\n` counts once
+            if self.ch == b'\n' || (self.ch == b'\r' && self.input[self.next_pos] != b'\n') {
+                self.line += 1;
+                self.column = 0;
+            }
+            // continuation bytes belong to the column of their first byte
+            if self.input[self.next_pos] & 0xC0 != 0x80 {
+                self.column += 1;
+            }
             self.ch = self.input[self.next_pos];
             self.pos = self.next_pos;
             self.next_pos += 1;
@@ -32,109 +69,335 @@ impl Lexer {
         }
     }
 
+    fn peek_char(&self) -> u8 {
+        self.char_at(1)
+    }
+
+    fn char_at(&self, offset: usize) -> u8 {
+        match self.input.get(self.pos + offset) {
+            Some(ch) => *ch,
+            None => 0,
+        }
+    }
+
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
-        let tok = match self.ch {
+        if !self.trivia {
+            self.skip_whitespace();
+        }
+        let start = self.pos;
+        let line = self.line;
+        let column = self.column;
+
+        let kind = match self.ch {
             b'=' => {
-                Token::Assign
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::EQ
+                } else if self.peek_char() == b'>' {
+                    self.read_char();
+                    TokenKind::Arrow
+                } else {
+                    TokenKind::Assign
+                }
+            }
+            b'!' => {
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::NotEQ
+                } else {
+                    TokenKind::Bang
+                }
+            }
+            b'&' => {
+                if self.peek_char() == b'&' {
+                    self.read_char();
+                    TokenKind::And
+                } else {
+                    TokenKind::Illegal
+                }
+            }
+            b'|' => {
+                if self.peek_char() == b'|' {
+                    self.read_char();
+                    TokenKind::Or
+                } else if self.peek_char() == b'>' {
+                    self.read_char();
+                    TokenKind::Pipe
+                } else {
+                    TokenKind::Illegal
+                }
+            }
+            b'%' => {
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::PercentAssign
+                } else {
+                    TokenKind::Percent
+                }
             }
             b'+' => {
-                Token::Plus
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::PlusAssign
+                } else {
+                    TokenKind::Plus
+                }
             }
             b'-' => {
-                Token::Minus
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::MinusAssign
+                } else {
+                    TokenKind::Minus
+                }
             }
             b'*' => {
-                Token::Star
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::StarAssign
+                } else {
+                    TokenKind::Star
+                }
             }
             b'/' => {
-                Token::Slash
+                match self.peek_char() {
+                    b'/' if self.is_doc_comment() => {
+                        TokenKind::DocComment(self.read_doc_comment())
+                    }
+                    // plain comments only get here in lossless mode,
+                    // otherwise skip_whitespace consumes them
+                    b'/' => {
+                        TokenKind::Comment(self.read_line_comment())
+                    }
+                    b'*' => {
+                        match self.block_comment_len() {
+                            Some(len) => {
+                                TokenKind::Comment(self.read_block_comment(len))
+                            }
+                            None => {
+                                self.skip_to_eof();
+                                TokenKind::Error("unterminated block comment".to_string())
+                            }
+                        }
+                    }
+                    b'=' => {
+                        self.read_char();
+                        TokenKind::SlashAssign
+                    }
+                    _ => {
+                        TokenKind::Slash
+                    }
+                }
+            }
+            b' ' | b'\t' => {
+                TokenKind::Whitespace(self.read_whitespace())
             }
             b'>' => {
-                Token::GT
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::GTE
+                } else {
+                    TokenKind::GT
+                }
             }
             b'<' => {
-                Token::LT
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::LTE
+                } else {
+                    TokenKind::LT
+                }
             }
             b'\n' => {
-                Token::NewLine
+                TokenKind::NewLine
+            }
+            b'\r' => {
+                if self.peek_char() == b'\n' {
+                    self.read_char();
+                }
+                TokenKind::NewLine
+            }
+            // byte order mark and shebang line only get here in lossless
+            // mode, otherwise skip_whitespace consumes them
+            0xEF if self.at_bom() => {
+                self.read_char();
+                self.read_char();
+                self.column = 0;
+                TokenKind::Whitespace("\u{FEFF}".to_string())
+            }
+            b'#' if self.at_shebang() => {
+                TokenKind::Comment(self.read_line_comment())
             }
             b'(' => {
-                Token::LPAREN
+                TokenKind::LPAREN
             }
             b')' => {
-                Token::RPAREN
+                TokenKind::RPAREN
             }
             b'{' => {
-                Token::LBRACE
+                if let Some(depth) = self.templates.last_mut() {
+                    *depth += 1;
+                }
+                TokenKind::LBRACE
             }
             b'}' => {
-                Token::RBRACE
+                match self.templates.last_mut() {
+                    Some(0) => {
+                        self.templates.pop();
+                        self.read_template_part(false)
+                    }
+                    Some(depth) => {
+                        *depth -= 1;
+                        TokenKind::RBRACE
+                    }
+                    None => {
+                        TokenKind::RBRACE
+                    }
+                }
             }
             b',' => {
-                Token::Comma
+                TokenKind::Comma
+            }
+            b';' => {
+                TokenKind::Semicolon
+            }
+            b':' => {
+                TokenKind::Colon
+            }
+            b'.' if self.peek_char() == b'.' && self.char_at(2) == b'.' => {
+                self.read_char();
+                self.read_char();
+                TokenKind::Ellipsis
+            }
+            b'.' if self.peek_char() == b'.' => {
+                self.read_char();
+                if self.peek_char() == b'=' {
+                    self.read_char();
+                    TokenKind::DotDotEq
+                } else {
+                    TokenKind::DotDot
+                }
+            }
+            b'.' => {
+                TokenKind::Dot
             }
             b'[' => {
-                Token::LBRACKET
+                TokenKind::LBRACKET
             }
             b']' => {
-                Token::RBRACKET
+                TokenKind::RBRACKET
             }
             b'"' => {
-                let val = self.read_str();
-                Token::String(val)
+                if self.peek_char() == b'"' && self.char_at(2) == b'"' {
+                    self.read_multiline_str()
+                } else {
+                    self.read_template_part(true)
+                }
             }
 
             0 => {
-                Token::EOF
+                TokenKind::EOF
+            }
+            b'r' if self.raw_str_hashes().is_some() => {
+                self.read_raw_str()
             }
             _ => {
-                if is_char(self.ch) {
+                if is_ident_start(self.current_char()) {
                     let val = self.read_ident();
-                    match val.as_str() {
+                    match &*val {
                         "func" => {
-                            Token::Func
+                            TokenKind::Func
                         }
                         "var" => {
-                            Token::Var
+                            TokenKind::Var
                         }
                         "true" => {
-                            Token::Bool(true)
+                            TokenKind::Bool(true)
                         }
                         "false" => {
-                            Token::Bool(false)
+                            TokenKind::Bool(false)
                         }
                         "return" => {
-                            Token::Return
+                            TokenKind::Return
                         }
                         "if" => {
-                            Token::If
+                            TokenKind::If
                         }
                         "else" => {
-                            Token::Else
+                            TokenKind::Else
+                        }
+                        "while" => {
+                            TokenKind::While
+                        }
+                        "for" => {
+                            TokenKind::For
+                        }
+                        "in" => {
+                            TokenKind::In
+                        }
+                        "break" => {
+                            TokenKind::Break
+                        }
+                        "continue" => {
+                            TokenKind::Continue
+                        }
+                        "match" => {
+                            TokenKind::Match
                         }
                         _ => {
-                            Token::Ident(val)
+                            TokenKind::Ident(val)
                         }
                     }
                 } else if is_digit(self.ch) {
-                    Token::Int(self.read_number())
+                    self.read_number()
                 } else {
-                    Token::Illegal
+                    self.skip_rest_of_char();
+                    TokenKind::Illegal
                 }
             }
         };
         
         self.read_char();
-        tok
+        Token::new(kind, Span {
+            offset: start,
+            line,
+            column,
+            len: self.pos - start,
+        })
     }
 
     fn skip_whitespace(&mut self) {
         loop {
-            match self.ch {
-                b' ' | b'\t' => {
+            match (self.ch, self.peek_char()) {
+                (b' ' | b'\t', _) => {
+                    self.read_char();
+                }
+                (0xEF, _) if self.at_bom() => {
+                    self.read_char();
+                    self.read_char();
+                    // the mark is invisible, so it takes up no column
+                    self.column = 0;
+                    self.read_char();
+                }
+                (b'#', b'!') if self.at_shebang() => {
+                    self.read_line_comment();
                     self.read_char();
                 }
+                (b'/', b'/') if !self.is_doc_comment() => {
+                    self.read_line_comment();
+                    self.read_char();
+                }
+                (b'/', b'*') => {
+                    match self.block_comment_len() {
+                        Some(len) => {
+                            self.read_block_comment(len);
+                            self.read_char();
+                        }
+                        None => {
+                            break;
+                        }
+                    }
+                }
                 _ => {
                     break;
                 }
@@ -142,54 +405,485 @@ impl Lexer {
         }
     }
 
-    fn read_ident(&mut self) -> String {
-        let mut str = String::new();
-        while is_char(self.ch) {
-            str = str + &(self.ch as char).to_string();
-            self.read_char(); 
+    fn read_whitespace(&mut self) -> String {
+        let start = self.pos;
+        while self.peek_char() == b' ' || self.peek_char() == b'\t' {
+            self.read_char();
         }
-        self.back();
 
-        str
+        String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string()
+    }
+
+    fn read_line_comment(&mut self) -> String {
+        let start = self.pos;
+        while !matches!(self.peek_char(), b'\n' | b'\r' | 0) {
+            self.read_char();
+        }
+
+        String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string()
+    }
+
+    fn read_block_comment(&mut self, len: usize) -> String {
+        let start = self.pos;
+        for _ in 1..len {
+            self.read_char();
+        }
+
+        String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string()
+    }
+
+    fn at_bom(&self) -> bool {
+        self.pos == 0 && self.input.starts_with(BOM)
+    }
+
+    /// A `#!` line is only allowed at the very start of the input,
+    /// after an optional byte order mark.
+    fn at_shebang(&self) -> bool {
+        let start = if self.input.starts_with(BOM) { BOM.len() } else { 0 };
+        self.pos == start && self.ch == b'#' && self.peek_char() == b'!'
+    }
+
+    fn is_doc_comment(&self) -> bool {
+        self.ch == b'/' && self.peek_char() == b'/' && self.char_at(2) == b'/' && self.char_at(3) != b'/'
+    }
+
+    /// Length of the block comment starting at the current position,
+    /// or None if it is never closed. Block comments nest.
+    fn block_comment_len(&self) -> Option<usize> {
+        let mut depth = 0;
+        let mut i = self.pos;
+        while i + 1 < self.input.len() {
+            match (self.input[i], self.input[i + 1]) {
+                (b'/', b'*') => {
+                    depth += 1;
+                    i += 2;
+                }
+                (b'*', b'/') => {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        return Some(i - self.pos)
+                    }
+                }
+                _ => {
+                    i += 1;
+                }
+            }
+        }
+        None
+    }
+
+    fn read_doc_comment(&mut self) -> String {
+        self.read_char();
+        self.read_char();
+        let start = self.next_pos;
+        while !matches!(self.peek_char(), b'\n' | b'\r' | 0) {
+            self.read_char();
+        }
+
+        String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string()
+    }
+
+    fn skip_to_eof(&mut self) {
+        while self.peek_char() != 0 {
+            self.read_char();
+        }
     }
-    fn read_number(&mut self) -> i64 {
-        let mut str = String::new(); 
-        while is_digit(self.ch) {
-            str = str + &(self.ch as char).to_string(); 
 
+    fn read_ident(&mut self) -> Rc<str> {
+        let start = self.pos;
+        self.skip_rest_of_char();
+        while is_ident_continue(self.decode_char(self.next_pos)) {
             self.read_char(); 
+            self.skip_rest_of_char();
+        }
+
+        let name = String::from_utf8_lossy(&self.input[start..self.next_pos]);
+        match self.symbols.get(&*name) {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let symbol: Rc<str> = Rc::from(&*name);
+                self.symbols.insert(symbol.clone());
+                symbol
+            }
+        }
+    }
+    fn read_number(&mut self) -> TokenKind {
+        let start = self.pos;
+        if self.ch == b'0' {
+            let radix = match self.peek_char() {
+                b'x' | b'X' => 16,
+                b'o' | b'O' => 8,
+                b'b' | b'B' => 2,
+                _ => 10,
+            };
+            if radix != 10 {
+                self.read_char();
+                return self.read_radix_number(start, radix)
+            }
+        }
+
+        let mut is_float = false;
+        self.read_digits();
+        if self.peek_char() == b'.' && is_digit(self.char_at(2)) {
+            is_float = true;
+            self.read_char();
+            self.read_digits();
+        }
+        if matches!(self.peek_char(), b'e' | b'E') {
+            let exp_digit = match self.char_at(2) {
+                b'+' | b'-' => self.char_at(3),
+                ch => ch,
+            };
+            if is_digit(exp_digit) {
+                is_float = true;
+                self.read_char();
+                if matches!(self.peek_char(), b'+' | b'-') {
+                    self.read_char();
+                }
+                self.read_digits();
+            }
+        }
+
+        let literal = String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string();
+        let digits = literal.replace('_', "");
+        if is_float {
+            match digits.parse::<f64>() {
+                Ok(f) if f.is_finite() => TokenKind::Float(f),
+                _ => TokenKind::Error(format!("float literal `{}` is out of range", literal)),
+            }
+        } else {
+            match digits.parse::<i64>() {
+                Ok(i) => TokenKind::Int(i),
+                Err(_) => TokenKind::Error(format!("integer literal `{}` is too large", literal)),
+            }
         }
-        self.back();
+    }
 
-        str.parse().unwrap()
+    /// Reads the digits of a `0x`, `0o` or `0b` literal. The current
+    /// character is the radix letter.
+    fn read_radix_number(&mut self, start: usize, radix: u32) -> TokenKind {
+        let digits_start = self.next_pos;
+        while self.peek_char().is_ascii_alphanumeric() || self.peek_char() == b'_' {
+            self.read_char();
+        }
+        let literal = String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string();
+        let digits = String::from_utf8_lossy(&self.input[digits_start..self.next_pos]).replace('_', "");
+
+        if digits.is_empty() {
+            return TokenKind::Error(format!("integer literal `{}` has no digits", literal))
+        }
+        if !digits.chars().all(|ch| ch.is_digit(radix)) {
+            return TokenKind::Error(format!("invalid digit in base {} literal `{}`", radix, literal))
+        }
+        match i64::from_str_radix(&digits, radix) {
+            Ok(i) => TokenKind::Int(i),
+            Err(_) => TokenKind::Error(format!("integer literal `{}` is too large", literal)),
+        }
+    }
+
+    fn read_digits(&mut self) {
+        while is_digit(self.peek_char()) || self.peek_char() == b'_' {
+            self.read_char();
+        }
+    }
+    /// Reads the text of a string literal up to the closing quote, or up to
+    /// a `${` that starts an interpolation. `start` is true at the opening
+    /// quote and false at the `}` that closes an interpolation.
+    fn read_template_part(&mut self, start: bool) -> TokenKind {
+        match (self.read_str(), start) {
+            (Ok((val, false)), true) => {
+                TokenKind::String(val)
+            }
+            (Ok((val, true)), true) => {
+                TokenKind::TemplateStart(val)
+            }
+            (Ok((val, true)), false) => {
+                TokenKind::TemplateMiddle(val)
+            }
+            (Ok((val, false)), false) => {
+                TokenKind::TemplateEnd(val)
+            }
+            (Err(msg), _) => {
+                TokenKind::Error(msg)
+            }
+        }
     }
-    fn read_str(&mut self) -> String {
-        let mut str = String::new();
+
+    /// Reads a string literal. On a bad escape the rest of the literal is
+    /// still consumed so lexing can continue after the closing quote.
+    /// The flag is true when the text ended at a `${` instead of a quote.
+    fn read_str(&mut self) -> Result<(String, bool), String> {
+        let mut bytes = Vec::new();
+        let mut error = None;
+        let mut interpolation = false;
         self.read_char();
         while self.ch != b'"' {
-            str = str + &(self.ch as char).to_string();
+            match self.ch {
+                0 => {
+                    return Err("unterminated string literal".to_string())
+                }
+                b'\r' => {
+                    if self.peek_char() != b'\n' {
+                        bytes.push(b'\n');
+                    }
+                }
+                b'$' if self.peek_char() == b'{' => {
+                    self.read_char();
+                    self.templates.push(0);
+                    interpolation = true;
+                    break;
+                }
+                b'\\' => {
+                    self.read_char();
+                    match self.read_escape() {
+                        Ok(ch) => {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
+                        Err(msg) => {
+                            error.get_or_insert(msg);
+                        }
+                    }
+                }
+                _ => {
+                    bytes.push(self.ch);
+                }
+            }
             self.read_char();
         }
-        str
+
+        match error {
+            Some(msg) => Err(msg),
+            None => Ok((String::from_utf8_lossy(&bytes).to_string(), interpolation)),
+        }
     }
 
-    fn back(&mut self) {
-        if self.next_pos > 0 {
-            self.pos -= 1;
-            self.next_pos -= 1;
-            self.ch = self.input[self.pos];
+    /// Number of `#`s after the `r` of a raw string, or None if the current
+    /// `r` does not start a raw string.
+    fn raw_str_hashes(&self) -> Option<usize> {
+        let mut hashes = 0;
+        while self.char_at(1 + hashes) == b'#' {
+            hashes += 1;
+        }
+        if self.char_at(1 + hashes) == b'"' {
+            Some(hashes)
         } else {
-            self.ch = 0;
+            None
         }
     }
 
+    /// Reads `r"..."` or `r#"..."#`. The text is taken verbatim and ends at
+    /// a quote followed by as many `#`s as the opening had.
+    fn read_raw_str(&mut self) -> TokenKind {
+        let hashes = self.raw_str_hashes().unwrap_or(0);
+        for _ in 0..hashes + 1 {
+            self.read_char();
+        }
+
+        let mut closing = vec![b'"'];
+        closing.resize(hashes + 1, b'#');
+        let start = self.next_pos;
+        let mut end = start;
+        while end < self.input.len() && !self.input[end..].starts_with(&closing) {
+            end += 1;
+        }
+        if end == self.input.len() {
+            self.skip_to_eof();
+            return TokenKind::Error("unterminated raw string literal".to_string())
+        }
+
+        while self.pos < end + hashes {
+            self.read_char();
+        }
+        let text = String::from_utf8_lossy(&self.input[start..end]);
+        TokenKind::String(text.replace("\r\n", "\n").replace('\r', "\n"))
+    }
+
+    /// Reads a `"""` string. A newline right after the opening quotes and a
+    /// closing line holding only whitespace are dropped, and the indentation
+    /// shared by all non-blank lines is removed. Escapes work as in normal
+    /// strings; `${` is not special.
+    fn read_multiline_str(&mut self) -> TokenKind {
+        self.read_char();
+        self.read_char();
+
+        let start = self.next_pos;
+        let mut end = start;
+        while end + 2 < self.input.len() && &self.input[end..end + 3] != b"\"\"\"" {
+            if self.input[end] == b'\\' {
+                end += 1;
+            }
+            end += 1;
+        }
+        if end + 2 >= self.input.len() {
+            self.skip_to_eof();
+            return TokenKind::Error("unterminated multi-line string literal".to_string())
+        }
+
+        let raw = String::from_utf8_lossy(&self.input[start..end]).to_string();
+        let mut content_start = start;
+        let mut content_end = end;
+        if raw.starts_with('\n') {
+            content_start += 1;
+        } else if raw.starts_with("\r\n") {
+            content_start += 2;
+        }
+        if let Some(last_newline) = raw.rfind('\n') {
+            if raw[last_newline + 1..].trim_matches([' ', '\t']).is_empty() && start + last_newline >= content_start {
+                content_end = start + last_newline;
+            }
+        }
+        let indent = String::from_utf8_lossy(&self.input[content_start..content_end])
+            .split('\n')
+            .filter(|line| !line.trim_matches([' ', '\t', '\r']).is_empty())
+            .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+            .min()
+            .unwrap_or(0);
+
+        while self.next_pos < content_start {
+            self.read_char();
+        }
+        let mut bytes = Vec::new();
+        let mut error = None;
+        let mut skipped = 0;
+        while self.next_pos < content_end {
+            self.read_char();
+            if skipped < indent && (self.ch == b' ' || self.ch == b'\t') {
+                skipped += 1;
+                continue;
+            }
+            skipped = indent;
+            match self.ch {
+                b'\n' => {
+                    bytes.push(b'\n');
+                    skipped = 0;
+                }
+                b'\r' => {
+                    // `\r\n` is kept as `\n`, and the line break before the
+                    // closing quotes is dropped entirely
+                    if self.peek_char() != b'\n' && self.next_pos < content_end {
+                        bytes.push(b'\n');
+                        skipped = 0;
+                    }
+                }
+                b'\\' => {
+                    self.read_char();
+                    match self.read_escape() {
+                        Ok(ch) => {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
+                        Err(msg) => {
+                            error.get_or_insert(msg);
+                        }
+                    }
+                }
+                _ => {
+                    bytes.push(self.ch);
+                }
+            }
+        }
+        while self.pos < end + 2 {
+            self.read_char();
+        }
+
+        match error {
+            Some(msg) => TokenKind::Error(msg),
+            None => TokenKind::String(String::from_utf8_lossy(&bytes).to_string()),
+        }
+    }
+
+    fn read_escape(&mut self) -> Result<char, String> {
+        match self.ch {
+            b'n' => Ok('\n'),
+            b't' => Ok('\t'),
+            b'r' => Ok('\r'),
+            b'"' => Ok('"'),
+            b'$' => Ok('$'),
+            b'\\' => Ok('\\'),
+            b'u' => self.read_unicode_escape(),
+            0 => Err("unterminated string literal".to_string()),
+            _ => Err(format!("unknown escape sequence `\\{}`", self.current_char())),
+        }
+    }
+
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        if self.peek_char() != b'{' {
+            return Err("expected `{` after `\\u`".to_string())
+        }
+        self.read_char();
+
+        let start = self.next_pos;
+        while self.peek_char().is_ascii_hexdigit() {
+            self.read_char();
+        }
+        let digits = String::from_utf8_lossy(&self.input[start..self.next_pos]).to_string();
+        if self.peek_char() != b'}' {
+            return Err(format!("unterminated unicode escape `\\u{{{}`", digits))
+        }
+        self.read_char();
+
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(format!("invalid unicode escape `\\u{{{}}}`", digits))
+        }
+        match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+            Some(ch) => Ok(ch),
+            None => Err(format!("`\\u{{{}}}` is not a valid unicode scalar value", digits)),
+        }
+    }
+
+    fn current_char(&self) -> char {
+        self.decode_char(self.pos)
+    }
+
+    fn decode_char(&self, pos: usize) -> char {
+        let end = usize::min(pos + 4, self.input.len());
+        if pos >= end {
+            return '\0'
+        }
+        // the window may cut the following character in half
+        let bytes = &self.input[pos..end];
+        let valid = match std::str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+        };
+        valid.chars().next().unwrap_or('\0')
+    }
+
+    /// Moves to the last byte of a multibyte character so the next
+    /// read_char starts on a character boundary.
+    fn skip_rest_of_char(&mut self) {
+        for _ in 1..self.current_char().len_utf8() {
+            self.read_char();
+        }
+    }
+
+}
+
+impl Iterator for Lexer {
+    type Item = Token;
+
+    /// Yields tokens until the end of the input. The EOF token itself is not
+    /// yielded.
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.next_token();
+        match tok.kind {
+            TokenKind::EOF => None,
+            _ => Some(tok),
+        }
+    }
 }
 
 
-fn is_char(ch: u8) -> bool {
-    return b'a' <= ch && ch <= b'z' || b'A' <= ch && ch <= b'Z';
+fn is_ident_start(ch: char) -> bool {
+    ch == '_' || UnicodeXID::is_xid_start(ch)
+}
+
+fn is_ident_continue(ch: char) -> bool {
+    UnicodeXID::is_xid_continue(ch)
 }
 
 fn is_digit(ch: u8) -> bool {
-    return b'0' <= ch && ch <= b'9'; 
+    ch.is_ascii_digit()
 }
@@ -1,3 +1,6 @@
+#![allow(clippy::module_inception, clippy::needless_return, clippy::single_match)]
+#![cfg_attr(test, allow(clippy::useless_vec))]
+
 pub mod lexer;
 pub mod parser;
 pub mod token;
@@ -9,25 +12,27 @@ pub mod object;
 
 #[cfg(test)]
 mod tests{
-    use crate::{lexer::lexer::Lexer, parser::parser::Parser, ast::ast::{Statement, Ident, Expression, Literals, Infix, Prefix}};
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{eval::eval::Eval, object::{env::Env, object::Object}, lexer::lexer::Lexer, parser::parser::Parser, ast::ast::{Statement, Ident, Expression, Literals, Infix, Prefix}, token::token::{Span, Token, TokenKind}};
 
 #[test]
     fn lexer() {
         let input = "var x = 10\n var y = true\n var z = -19 + 29\n var a = 10 < 20";
         let lexer = Lexer::new(input.to_string());
         let mut parser = Parser::new(lexer);
-        let stmts = parser.parse_program();
+        let stmts = parser.parse_program().unwrap();
         let res = vec![
             Statement::Var(
-            Ident{literal: "x".to_string()},
+            Ident{literal: "x".into()},
             Expression::Literal(Literals::Int(10))
             ),
             Statement::Var(
-            Ident { literal: "y".to_string() },
+            Ident { literal: "y".into() },
             Expression::Literal(Literals::Bool(true))
             ),
             Statement::Var(
-            Ident { literal: "z".to_string() },
+            Ident { literal: "z".into() },
             Expression::Infix(
             Infix::Plus,
             Box::new(Expression::Prefix(Prefix::Minus, 
@@ -35,7 +40,7 @@ mod tests{
             Box::new(Expression::Literal(Literals::Int(29))))
             ),
             Statement::Var(
-            Ident { literal: "a".to_string() },
+            Ident { literal: "a".into() },
             Expression::Infix(
             Infix::LT, 
             Box::new(Expression::Literal(Literals::Int(10))),
@@ -49,4 +54,764 @@ mod tests{
             assert_eq!(stmts[i], res[i]);
         }
     }   
+
+    #[test]
+    fn token_spans() {
+        let mut lexer = Lexer::new("var x = 10\n  x + 2".to_string());
+        let mut tokens = Vec::new();
+        loop {
+            let tok = lexer.next_token();
+            if tok.kind == TokenKind::EOF {
+                break;
+            }
+            tokens.push(tok);
+        }
+
+        assert_eq!(tokens[3].kind, TokenKind::Int(10));
+        assert_eq!(tokens[3].span, Span { offset: 8, line: 1, column: 9, len: 2 });
+        assert_eq!(tokens[4].kind, TokenKind::NewLine);
+        assert_eq!(tokens[5].kind, TokenKind::Ident("x".into()));
+        assert_eq!(tokens[5].span, Span { offset: 13, line: 2, column: 3, len: 1 });
+    }
+
+    #[test]
+    fn identifiers_are_interned() {
+        let mut parser = Parser::new(Lexer::new("var x = 1\nx + x".to_string()));
+        let stmts = parser.parse_program().unwrap();
+        let (Statement::Var(decl, _), Statement::ExpressionStmt(Expression::Infix(_, left, right))) = (&stmts[0], &stmts[1]) else {
+            panic!("unexpected program {:?}", stmts)
+        };
+        let (Expression::Ident(left), Expression::Ident(right)) = (left.as_ref(), right.as_ref()) else {
+            panic!("unexpected operands {:?} and {:?}", left, right)
+        };
+        assert!(Rc::ptr_eq(&decl.literal, &left.literal));
+        assert!(Rc::ptr_eq(&left.literal, &right.literal));
+    }
+
+    fn token_kinds(input: &str) -> Vec<TokenKind> {
+        Lexer::new(input.to_string()).map(|tok| tok.kind).collect()
+    }
+
+    #[test]
+    fn comments() {
+        let input = "// line\n/* outer /* inner */ still outer */ x /// docs\n//// not docs\ny / 2";
+        assert_eq!(token_kinds(input), vec![
+            TokenKind::NewLine,
+            TokenKind::Ident("x".into()),
+            TokenKind::DocComment(" docs".to_string()),
+            TokenKind::NewLine,
+            TokenKind::NewLine,
+            TokenKind::Ident("y".into()),
+            TokenKind::Slash,
+            TokenKind::Int(2),
+        ]);
+
+        let mut lexer = Lexer::new("x /* never closed".to_string());
+        lexer.next_token();
+        assert_eq!(lexer.next_token().kind, TokenKind::Error("unterminated block comment".to_string()));
+        assert_eq!(lexer.next_token().kind, TokenKind::EOF);
+
+        let input = "var x = 1 /// doc\nvar xs = [\n  1,\n  /// two\n  2\n]\nvar add = func(a, b) { a + b }\nadd(x,\n  /// args\n  len(xs))";
+        assert_eq!(eval(input).to_string(), "3");
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            token_kinds(r#""größe\t\"ok\"\\ \u{1F600}\n""#),
+            vec![TokenKind::String("größe\t\"ok\"\\ \u{1F600}\n".to_string())]
+        );
+        assert_eq!(
+            token_kinds(r#""bad \q escape" x"#),
+            vec![
+                TokenKind::Error("unknown escape sequence `\\q`".to_string()),
+                TokenKind::Ident("x".into()),
+            ]
+        );
+        assert_eq!(
+            token_kinds(r#""\u{110000}""#),
+            vec![TokenKind::Error("`\\u{110000}` is not a valid unicode scalar value".to_string())]
+        );
+        assert_eq!(
+            token_kinds("x = \"never closed"),
+            vec![
+                TokenKind::Ident("x".into()),
+                TokenKind::Assign,
+                TokenKind::Error("unterminated string literal".to_string()),
+            ]
+        );
+    }
+
+    fn parse_errors(input: &str) -> Vec<String> {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program().unwrap_err().iter().map(|err| err.to_string()).collect()
+    }
+
+    fn eval(input: &str) -> Object {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program().unwrap();
+        let mut eval = Eval::new(Rc::new(RefCell::new(Env::new())));
+        eval.eval_program(program)
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(token_kinds("== != <= >= ! && || % = < >"), vec![
+            TokenKind::EQ,
+            TokenKind::NotEQ,
+            TokenKind::LTE,
+            TokenKind::GTE,
+            TokenKind::Bang,
+            TokenKind::And,
+            TokenKind::Or,
+            TokenKind::Percent,
+            TokenKind::Assign,
+            TokenKind::LT,
+            TokenKind::GT,
+        ]);
+
+        let cases = [
+            ("1 + 2 == 3", "true"),
+            ("1 != 1", "false"),
+            ("2 <= 2 && 3 >= 4", "false"),
+            ("1 > 2 || !false", "true"),
+            ("!true == false", "true"),
+            ("17 % 5", "2"),
+            ("\"a\" <= \"b\"", "true"),
+            ("true == false", "false"),
+            ("false && missing", "false"),
+            ("1 == 1 || missing", "true"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert!(matches!(eval("1 % 0"), Object::Error(_)));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(token_kinds("1.5 2e-3 0xFF 0b1010 0o17 1_000_000"), vec![
+            TokenKind::Float(1.5),
+            TokenKind::Float(2e-3),
+            TokenKind::Int(255),
+            TokenKind::Int(10),
+            TokenKind::Int(15),
+            TokenKind::Int(1_000_000),
+        ]);
+        assert_eq!(token_kinds("99999999999999999999 0b12"), vec![
+            TokenKind::Error("integer literal `99999999999999999999` is too large".to_string()),
+            TokenKind::Error("invalid digit in base 2 literal `0b12`".to_string()),
+        ]);
+
+        assert_eq!(parse_errors("var x = 1e999"), vec!["1:9: float literal `1e999` is out of range"]);
+
+        assert_eq!(eval("1.5 * 2 + 0x10").to_string(), "19");
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(token_kinds("x1 my_var _tmp größe 名前 1x"), vec![
+            TokenKind::Ident("x1".into()),
+            TokenKind::Ident("my_var".into()),
+            TokenKind::Ident("_tmp".into()),
+            TokenKind::Ident("größe".into()),
+            TokenKind::Ident("名前".into()),
+            TokenKind::Int(1),
+            TokenKind::Ident("x".into()),
+        ]);
+        assert_eq!(token_kinds("a € b"), vec![
+            TokenKind::Ident("a".into()),
+            TokenKind::Illegal,
+            TokenKind::Ident("b".into()),
+        ]);
+        assert_eq!(eval("var größe_2 = 3\ngröße_2 * 2").to_string(), "6");
+    }
+
+    #[test]
+    fn lossless() {
+        let input = "var größe = 0x1F /* nested /* block */ */\n\t// note\n/// doc\nprint(\"a\\tb\" + x) \"open";
+        let tokens: Vec<_> = Lexer::new_lossless(input.to_string()).collect();
+        let rebuilt: String = tokens.iter().map(|tok| tok.span.slice(input)).collect();
+        assert_eq!(rebuilt, input);
+
+        assert!(tokens.contains(&Token::new(
+            TokenKind::Comment("// note".to_string()),
+            Span { offset: 45, line: 2, column: 2, len: 7 },
+        )));
+        assert!(tokens.iter().any(|tok| tok.kind == TokenKind::Whitespace("\t".to_string())));
+        assert!(!token_kinds(input).iter().any(|kind| matches!(kind, TokenKind::Whitespace(_) | TokenKind::Comment(_))));
+    }
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(token_kinds(r#""a ${x + {1}} b ${"in ${y}"}!""#), vec![
+            TokenKind::TemplateStart("a ".to_string()),
+            TokenKind::Ident("x".into()),
+            TokenKind::Plus,
+            TokenKind::LBRACE,
+            TokenKind::Int(1),
+            TokenKind::RBRACE,
+            TokenKind::TemplateMiddle(" b ".to_string()),
+            TokenKind::TemplateStart("in ".to_string()),
+            TokenKind::Ident("y".into()),
+            TokenKind::TemplateEnd("".to_string()),
+            TokenKind::TemplateEnd("!".to_string()),
+        ]);
+
+        let input = "var a = 2\nvar b = 3\n\"total: ${a + b}, ${\"ok\"} \\${a}\"";
+        assert_eq!(eval(input).to_string(), "total: 5, ok ${a}");
+    }
+
+    #[test]
+    fn raw_and_multiline_strings() {
+        assert_eq!(token_kinds(r###"r"C:\dir" r#"{"a": "b"}"# r##"x"#y"## r"###), vec![
+            TokenKind::String(r"C:\dir".to_string()),
+            TokenKind::String(r#"{"a": "b"}"#.to_string()),
+            TokenKind::String(r##"x"#y"##.to_string()),
+            TokenKind::Ident("r".into()),
+        ]);
+        assert_eq!(token_kinds(r#"r#"open"#), vec![
+            TokenKind::Error("unterminated raw string literal".to_string()),
+        ]);
+
+        let input = "var s = \"\"\"\n    first\n      indented\\t\\\"\"\"\n\n    last\n    \"\"\"\ns";
+        assert_eq!(eval(input).to_string(), "first\n  indented\t\"\"\"\n\nlast");
+        assert_eq!(token_kinds("\"\"\"one line\"\"\""), vec![TokenKind::String("one line".to_string())]);
+    }
+
+    #[test]
+    fn line_endings_bom_and_shebang() {
+        let input = "\u{FEFF}#!/usr/bin/env monkey\r\nvar s = \"a\r\nb\" // note\r\nvar t = 1\rs";
+        assert_eq!(token_kinds(input), vec![
+            TokenKind::NewLine,
+            TokenKind::Var,
+            TokenKind::Ident("s".into()),
+            TokenKind::Assign,
+            TokenKind::String("a\nb".to_string()),
+            TokenKind::NewLine,
+            TokenKind::Var,
+            TokenKind::Ident("t".into()),
+            TokenKind::Assign,
+            TokenKind::Int(1),
+            TokenKind::NewLine,
+            TokenKind::Ident("s".into()),
+        ]);
+
+        let tokens: Vec<_> = Lexer::new(input.to_string()).collect();
+        assert_eq!(tokens[1].span, Span { offset: 26, line: 2, column: 1, len: 3 });
+        assert_eq!(tokens[11].span.line, 5);
+
+        let rebuilt: String = Lexer::new_lossless(input.to_string()).map(|tok| tok.span.slice(input).to_string()).collect();
+        assert_eq!(rebuilt, input);
+
+        assert_eq!(token_kinds("\u{FEFF}x")[0], TokenKind::Ident("x".into()));
+        assert_eq!(Lexer::new("\u{FEFF}x".to_string()).next_token().span.column, 1);
+        assert_eq!(token_kinds("x #!y"), vec![TokenKind::Ident("x".into()), TokenKind::Illegal, TokenKind::Bang, TokenKind::Ident("y".into())]);
+    }
+
+    #[test]
+    fn parse_errors_recover() {
+        let input = "var = 1\nvar ok = 2\nif (x { print(1) }\nprint(1, )\nvar y = *\nfunc(a, 1) { a }\nvar s = \"\\q\"";
+        assert_eq!(parse_errors(input), vec![
+            "1:5: expected identifier, found `=`",
+            "3:7: expected `)`, found `{`",
+            "5:9: expected expression, found `*`",
+            "6:9: expected identifier, found integer `1`",
+            "7:9: unknown escape sequence `\\q`",
+        ]);
+
+        let mut parser = Parser::new(Lexer::new("func() {\n  var 1\n  var x = \n}\nx)".to_string()));
+        let errors = parser.parse_program().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].expected, vec!["identifier".to_string()]);
+        assert_eq!(errors[0].found, TokenKind::Int(1));
+        assert_eq!(errors[0].span, Span { offset: 15, line: 2, column: 7, len: 1 });
+        assert_eq!(errors[1].found, TokenKind::NewLine);
+        assert_eq!(errors[2].found, TokenKind::RPAREN);
+
+        // an error inside a multi-line list or call skips to its end
+        assert_eq!(parse_errors("var xs = [\n  1 + *,\n  2,\n  3\n]\nvar = 1"), vec![
+            "2:7: expected expression, found `*`",
+            "6:5: expected identifier, found `=`",
+        ]);
+        assert_eq!(parse_errors("print(f(\n  1 *,\n  2\n))"), vec!["2:6: expected expression, found `,`"]);
+    }
+
+    #[test]
+    fn parser_terminates() {
+        let unclosed = ["{", "func() {", "[1, 2", "if (x) { 1", "f(1, 2", "\"a ${x", "a[1", "func(a, b", "x = ", "if (x) { 1 } else {"];
+        for input in unclosed {
+            assert!(!parse_errors(input).is_empty(), "{}", input);
+        }
+
+        let deep = [
+            "[".repeat(100_000),
+            format!("{}1", "-".repeat(100_000)),
+            "func() {".repeat(10_000),
+            "while (true) {".repeat(100_000),
+            "for x in y {".repeat(100_000),
+            format!("var {}a = 1", "[".repeat(200_000)),
+            format!("var {}a = 1", "{a: ".repeat(200_000)),
+            format!("match 1 {{ {}a => 1 }}", "[".repeat(200_000)),
+            format!("{}1{}", "[".repeat(129), "]".repeat(129)),
+        ];
+        for input in deep {
+            let errors = parse_errors(&input);
+            assert!(errors[0].contains("nested more than 128 levels deep"), "{:?}", errors);
+        }
+        let nested = format!("var x = {}1{}", "[".repeat(127), "]".repeat(127));
+        assert!(Parser::new(Lexer::new(nested)).parse_program().is_ok());
+
+        let mut parser = Parser::new_with_max_depth(Lexer::new("[[[1]]]\n[[[[1]]]]".to_string()), 4);
+        let errors = parser.parse_program().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span.line, 2);
+    }
+
+    #[test]
+    fn parser_random_input() {
+        let pieces = [
+            "var", "x", "y", "=", "==", "func", "(", ")", "{", "}", "[", "]", ",", "if", "else", "return",
+            "1", "2.5", "\"s\"", "\"a ${", "}\"", "+", "-", "*", "/", "!", "&&", "\n", "\n", " ", "/*", "*/", "//", "\\", "#", "\"", "é",
+        ];
+        // xorshift, so failures can be reproduced from the seed
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..2000 {
+            let len = random() % 80;
+            let mut input = String::new();
+            for _ in 0..len {
+                input += pieces[(random() % pieces.len() as u64) as usize];
+                input += " ";
+            }
+            // must return without hanging or panicking
+            let _ = Parser::new(Lexer::new(input)).parse_program();
+        }
+    }
+
+    #[test]
+    fn statement_termination() {
+        let cases = [
+            ("var a = 1; var b = 2; a + b", "3"),
+            ("var total = 1 +\n  2 *\n  3\ntotal", "7"),
+            ("var f = func(\n  a,\n  b,\n) {\n  return a - b\n}\nf(\n  5,\n  3\n)", "2"),
+            ("var xs = [\n  1,\n  2\n]\nxs[\n  1\n]", "2"),
+            ("var f = func(g) { return g() }\nf(func() {\n  var x = 4\n  return x\n}\n)", "4"),
+            ("if (\n  true &&\n  false\n) { return 1 } else { return 2 }", "2"),
+            ("var x = 1\n-1\nx", "1"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+
+        assert_eq!(parse_errors("var a = 1 var b = 2\nvar c = 3 4"), vec![
+            "1:11: expected newline or `;`, found `var`",
+            "2:11: expected newline or `;`, found integer `4`",
+        ]);
+        assert_eq!(parse_errors("f(func() { 1 2 })"), vec!["1:14: expected newline or `;`, found integer `2`"]);
+    }
+
+    #[test]
+    fn grouping_and_tuples() {
+        let cases = [
+            ("(1 + 2) * 3", "9"),
+            ("-(2 - 5)", "3"),
+            ("!(1 == 1)", "false"),
+            ("(1, \"a\", true)", "(1, a, true)"),
+            ("(1,)", "(1,)"),
+            ("()", "()"),
+            ("var pair = func(a, b) { return (b, a) }\npair(1, 2)[0]", "2"),
+            ("(1, (2, 3))[1][0]", "2"),
+            ("len((1, 2, 3))", "3"),
+            ("(1, [2, 3]) == (1, [2, 3])", "true"),
+            ("(1, 2) == (1, 2.0)", "true"),
+            ("(1, 2) != (2, 1)", "true"),
+            ("(1,) == (1, 1)", "false"),
+            ("[1, (2,)] == [1, (2,)]", "true"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert!(matches!(eval("(1, 2)[2]"), Object::Error(_)));
+        assert!(matches!(eval("(1, 2) + (3,)"), Object::Error(_)));
+
+        let mut parser = Parser::new(Lexer::new("(a)".to_string()));
+        assert_eq!(parser.parse_program().unwrap(), vec![Statement::ExpressionStmt(Expression::Ident(Ident { literal: "a".into() }))]);
+        assert_eq!(parse_errors("(1 2)"), vec!["1:4: expected `,` or `)`, found integer `2`"]);
+    }
+
+    #[test]
+    fn if_expressions() {
+        let grade = "var grade = func(n) {\n  return if (n >= 90) { \"a\" } else if (n >= 80) { \"b\" } else if (n >= 70) { \"c\" } else { \"f\" }\n}\n";
+        let cases = [
+            (format!("{grade}grade(95)"), "a"),
+            (format!("{grade}grade(85)"), "b"),
+            (format!("{grade}grade(75)"), "c"),
+            (format!("{grade}grade(10)"), "f"),
+            ("var x = if (1 < 2) { 1 } else { 2 }\nx".to_string(), "1"),
+            ("var x = if (false) { 1 }\nx".to_string(), ""),
+            ("if (true) { var a = 1\n a + 1 }".to_string(), "2"),
+            ("if (false) { 1 } else if (false) { 2 }".to_string(), ""),
+            ("var f = func() {\n  if (true) { return 1 }\n  return 2\n}\nf()".to_string(), "1"),
+            ("var f = func() {\n  if (true) {\n    if (true) { return 1 }\n  }\n  return 2\n}\nf()".to_string(), "1"),
+            ("var f = func() { var x = if (true) { return 1 } else { 2 }\n return 5 }\nf()".to_string(), "1"),
+            ("var f = func() { 1 + if (true) { return 7 } else { 0 } }\nf()".to_string(), "7"),
+            ("var f = func() { var xs = [1, if (true) { return 3 } else { 2 }]\n xs }\nf()".to_string(), "3"),
+            ("var f = func(a, b) { a }\nvar g = func() { f(if (true) { return 4 } else { 0 }, print(\"never\")) }\ng()".to_string(), "4"),
+            ("var n = 0\nfor i in 0..5 { var x = if (i == 2) { break } else { i }; n += 1 }\nn".to_string(), "2"),
+            ("var n = 0\nfor i in 0..5 { n += if (i == 2) { continue } else { 1 } }\nn".to_string(), "4"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+
+        let branches: Vec<String> = (0..100_000).map(|i| format!("if (x == {i}) {{ {i} }}")).collect();
+        let chain = format!("var x = 99999\n{} else {{ -1 }}", branches.join(" else "));
+        assert_eq!(eval(&chain).to_string(), "99999");
+
+        assert!(matches!(eval("if (missing) { 1 } else { 2 }"), Object::Error(_)));
+        assert_eq!(parse_errors("if (x) { 1 } else if { 2 }"), vec!["1:22: expected `(`, found `{`"]);
+    }
+
+    #[test]
+    fn loops() {
+        let cases = [
+            ("var i = 0\nwhile (i < 5) { var i = i + 1 }\ni", "5"),
+            ("var s = 0\nfor x in [1, 2, 3] { var s = s + x }\ns", "6"),
+            ("var s = \"\"\nfor c in \"héllo\" { var s = c + s }\ns", "olléh"),
+            ("var s = 0\nfor i in range(0, 10) {\n  if (i % 2 == 0) { continue }\n  if (i > 7) { break }\n  var s = s + i\n}\ns", "16"),
+            ("var n = 0\nwhile (true) { var n = n + 1; if (n == 3) { break } }\nn", "3"),
+            ("var n = 0\nouter: for i in range(0, 3) {\n  for j in range(0, 3) {\n    if (j == 1) { continue outer }\n    if (i == 2) { break outer }\n    var n = n + 1\n  }\n}\nn", "2"),
+            ("var f = func(xs) {\n  for x in xs { if (x > 1) { return x } }\n  return 0\n}\nf([1, 5, 7])", "5"),
+            ("var n = 0\nfor x in range(0, 100000) { var n = n + 1 }\nn", "100000"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert!(matches!(eval("for x in 5 { }"), Object::Error(_)));
+        assert!(matches!(eval("while (missing) { }"), Object::Error(_)));
+
+        assert_eq!(parse_errors("break\nwhile (true) { continue nope }\nfor x in [1] { func() { break } }\nx: 1\nbreak foo\ncontinue foo"), vec![
+            "1:1: `break` outside of a loop",
+            "2:25: unknown loop label `nope`",
+            "3:25: `break` outside of a loop",
+            "4:4: expected `while` or `for`, found integer `1`",
+            "5:1: `break` outside of a loop",
+            "6:1: `continue` outside of a loop",
+        ]);
+    }
+
+    #[test]
+    fn assignment() {
+        let cases = [
+            ("var x = 1\nx = 2\nx", "2"),
+            ("var x = 10\nx += 5; x -= 3; x *= 2; x %= 5\nx", "4"),
+            ("var x = 3\nx /= 2\nx", "1.5"),
+            ("var s = \"a\"\ns += \"b\"\ns", "ab"),
+            ("var xs = [1, 2, 3]\nxs[1] = 5\nxs[2] += 1\nxs", "[1, 5, 4]"),
+            ("var m = [[1, 2], [3, 4]]\nm[1][0] = 9\nm", "[[1, 2], [9, 4]]"),
+            ("var counter = func() {\n  var n = 0\n  return func() { n += 1; return n }\n}\nvar next = counter()\nnext(); next()\nnext()", "3"),
+            ("var x = 1\nvar f = func() { var x = 5; x = 6 }\nf()\nx", "1"),
+            ("var i = 0\nwhile (i < 3) { i += 1 }\ni", "3"),
+            ("var i = 0\nvar next = func() { i += 1; return i }\nvar xs = [0, 0, 0]\nxs[next()] += 10\n[xs, i]", "[[0, 10, 0], 1]"),
+            ("var i = -1\nvar next = func() { i += 1; return i }\nvar m = [[1, 2], [3, 4]]\nm[next()][next()] = 9\n[m, i]", "[[[1, 9], [3, 4]], 1]"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert_eq!(eval("y = 1").to_string(), "cannot assign to `y`, it is not declared");
+        assert_eq!(eval("var xs = [1]\nxs[3] = 1").to_string(), "index 3 is out of range for length 1");
+        assert!(matches!(eval("var t = (1, 2)\nt[0] = 3"), Object::Error(_)));
+
+        assert_eq!(token_kinds("+= -= *= /= %="), vec![
+            TokenKind::PlusAssign,
+            TokenKind::MinusAssign,
+            TokenKind::StarAssign,
+            TokenKind::SlashAssign,
+            TokenKind::PercentAssign,
+        ]);
+        assert_eq!(parse_errors("f() = 1\n1 + x = 2"), vec![
+            "1:1: only names and indexes can be assigned to",
+            "2:1: only names and indexes can be assigned to",
+        ]);
+    }
+
+    #[test]
+    fn hashes() {
+        let person = "var p = {\n  \"name\": \"ada\",\n  \"age\": 36,\n  1: true,\n  false: [1, 2],\n}\n";
+        let cases = [
+            (format!("{person}p"), "{name: ada, age: 36, 1: true, false: [1, 2]}"),
+            (format!("{person}p[\"name\"]"), "ada"),
+            (format!("{person}p[1 - 0]"), "true"),
+            (format!("{person}p[false][1]"), "2"),
+            (format!("{person}keys(p)"), "[name, age, 1, false]"),
+            (format!("{person}values(p)[1]"), "36"),
+            (format!("{person}has_key(p, \"age\")"), "true"),
+            (format!("{person}has_key(p, [1])"), "false"),
+            (format!("{person}len(delete(p, \"age\"))"), "3"),
+            (format!("{person}delete(p, 1)\nlen(p)"), "4"),
+            (format!("{person}p[\"age\"] += 1\np[\"city\"] = \"london\"\nkeys(p)"), "[name, age, 1, false, city]"),
+            (format!("{person}p[\"age\"] = 37\np[\"age\"]"), "37"),
+            ("{\"a\": 1, \"b\": 2} == {\"b\": 2, \"a\": 1}".to_string(), "true"),
+            ("{\"a\": 1} == {\"a\": 2}".to_string(), "false"),
+            ("{}".to_string(), "{}"),
+            ("var n = 0\nfor k in {1: 0, 2: 0} { n += k }\nn".to_string(), "3"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+        assert_eq!(eval("{[1]: 2}").to_string(), "[1] can't be used as a hash key");
+        assert_eq!(eval("{1: 2}[3]").to_string(), "key 3 not found in hash");
+        assert_eq!(parse_errors("{1 2}"), vec!["1:4: expected `:`, found integer `2`"]);
+    }
+
+    #[test]
+    fn arrow_functions_and_implicit_return() {
+        let cases = [
+            ("var add = (a, b) => a + b\nadd(2, 3)", "5"),
+            ("var double = x => x * 2\ndouble(4)", "8"),
+            ("var one = () => 1\none()", "1"),
+            ("var apply = (f, x) => f(x)\napply((x) => x - 1, 10)", "9"),
+            ("var adder = a => b => a + b\nadder(1)(2)", "3"),
+            ("var f = (x) => {\n  var y = x * 10\n  y + 1\n}\nf(2)", "21"),
+            ("var f = func(n) { if (n > 0) { \"pos\" } else { \"neg\" } }\nf(-1)", "neg"),
+            ("var f = func() { return 1; 2 }\nf()", "1"),
+            ("var f = func() { var x = 1 }\nf()", ""),
+            ("var f = func() { for x in [1] { x } }\nf()", ""),
+            ("var pair = () => (1, 2)\npair()[1]", "2"),
+            ("var f = x =>\n  x + 1\nf(1)", "2"),
+            ("match 2 {\n  1 => \"a\"\n  _ =>\n    \"b\"\n}", "b"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert_eq!(parse_errors("(a, 1) => a"), vec!["1:8: arrow function parameters must be names"]);
+    }
+
+    #[test]
+    fn function_params() {
+        let greet = "var greet = func(name, greeting = \"hello\", punct = greeting + \"!\") {\n  \"${greeting} ${name}${punct}\"\n}\n";
+        let sum = "var sum = func(first, ...rest) {\n  var total = first\n  for x in rest { total += x }\n  total\n}\n";
+        let cases = [
+            (format!("{greet}greet(\"ada\")"), "hello adahello!"),
+            (format!("{greet}greet(\"ada\", \"hi\", \"?\")"), "hi ada?"),
+            (format!("{greet}greet(\"ada\", punct: \".\")"), "hello ada."),
+            (format!("{greet}greet(punct: \"\", name: \"bob\")"), "hello bob"),
+            (format!("{sum}sum(1)"), "1"),
+            (format!("{sum}sum(1, 2, 3, 4)"), "10"),
+            ("var f = func(a, ...rest) { rest }\nf(1)".to_string(), "[]"),
+            ("var f = func(\n  a,\n  b = a * 2,\n) { b }\nf(\n  a: 4,\n)".to_string(), "8"),
+            ("var f = func(a, b) { return b }\nf(print(1), 2)".to_string(), "2"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+
+        let errors = [
+            (format!("{greet}greet(\"a\", nam: \"b\")"), "unknown argument `nam`"),
+            (format!("{greet}greet(\"a\", name: \"b\")"), "argument `name` is given twice"),
+            (format!("{greet}greet(greeting: \"hi\")"), "missing argument `name`"),
+            (format!("{greet}greet(1, 2, 3, 4)"), "Need at most 3 Arguments got 4"),
+            (format!("{sum}sum(rest: [1])"), "unknown argument `rest`"),
+            ("len(x: 1)".to_string(), "builtin functions don't take named arguments"),
+        ];
+        for (input, expected) in errors {
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+
+        assert_eq!(parse_errors("func(...a, b) {}\nfunc(a, a) {}\nf(a: 1, 2)"), vec![
+            "1:12: the rest parameter must be the last one",
+            "2:9: parameter `a` is declared twice",
+            "3:9: positional arguments must come before named ones",
+        ]);
+    }
+
+    #[test]
+    fn pipelines_and_methods() {
+        let cases = [
+            ("[1, 2, 3].len()", "3"),
+            ("\"abc\".len() + 1", "4"),
+            ("var xs = [1, 2]\nxs.len()", "2"),
+            ("var double = x => x * 2\n3 |> double", "6"),
+            ("var add = (a, b) => a + b\n1 |> add(2) |> add(3)", "6"),
+            ("var double = x => x * 2\n1 + 2 |> double", "6"),
+            ("var double = x => x * 2\n[1, 2] |>\n  len |>\n  double", "4"),
+            ("var add = (a, b) => a + b\n1.add(2).add(3)", "6"),
+            ("var f = func(a, b = 10) { a - b }\n15.f()", "5"),
+            ("var len = xs => 0\n[1].len()", "0"),
+            ("-[1, 2].len()", "-2"),
+            ("[1, 2] |> len == 2", "true"),
+            ("var add = (a, b) => a + b\n1 |> add(2) * 10 |> add(3)", "33"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert_eq!(eval("[1].nope()").to_string(), "no function `nope` to call on [1]");
+        assert_eq!(parse_errors("x.1\nx.y"), vec![
+            "1:3: expected identifier, found integer `1`",
+            "2:4: expected `(`, found end of file",
+        ]);
+    }
+
+    #[test]
+    fn ranges_and_slices() {
+        let cases = [
+            ("1..4", "1..4"),
+            ("1..=4", "1..5"),
+            ("len(0..10)", "10"),
+            ("var n = 3\nvar s = 0\nfor i in 0..n + 1 { s += i }\ns", "6"),
+            ("var s = 0\nfor i in 1..=3 { s += i }\ns", "6"),
+            ("1..3 == range(1, 3)", "true"),
+            ("var xs = [1, 2, 3, 4]\nxs[1:3]", "[2, 3]"),
+            ("var xs = [1, 2, 3, 4]\nxs[:2]", "[1, 2]"),
+            ("var xs = [1, 2, 3, 4]\nxs[2:]", "[3, 4]"),
+            ("var xs = [1, 2, 3, 4]\nxs[:]", "[1, 2, 3, 4]"),
+            ("var xs = [1, 2, 3, 4]\nxs[-3:-1]", "[2, 3]"),
+            ("var xs = [1, 2, 3, 4]\nxs[1..=2]", "[2, 3]"),
+            ("var xs = [1, 2, 3, 4]\nxs[-1]", "4"),
+            ("var xs = [1, 2, 3, 4]\nxs[-1] = 9\nxs", "[1, 2, 3, 9]"),
+            ("(1, 2, 3)[-2]", "2"),
+            ("(1, 2, 3)[1:]", "(2, 3)"),
+            ("\"héllo\"[1]", "é"),
+            ("\"héllo\"[2:]", "llo"),
+            ("\"héllo\"[-3:4]", "ll"),
+            ("\"abc\"[3:]", ""),
+            ("len(\"größe\")", "5"),
+            ("var s = \"größe\"\ns[len(s) - 1]", "e"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+
+        let errors = [
+            ("[1, 2][2]", "index 2 is out of range for length 2"),
+            ("[1, 2][-3]", "index -3 is out of range for length 2"),
+            ("\"ab\"[5]", "index 5 is out of range for length 2"),
+            ("[1, 2][1:3]", "slice 1:3 is out of range for length 2"),
+            ("[1, 2][-3:]", "slice -3: is out of range for length 2"),
+            ("[1, 2][2:1]", "slice 2:1 is out of range for length 2"),
+            ("\"abc\"[1:\"b\"]", "slice bounds must be Ints, got b"),
+            ("1[0]", "1 can't be indexed"),
+            ("\"a\"..\"b\"", ".. is not a valid Infix Operator for a .. b"),
+            ("len(-9223372036854775807..9223372036854775807)", "the length of -9223372036854775807..9223372036854775807 is too large"),
+        ];
+        for (input, expected) in errors {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert_eq!(token_kinds("0..1 ..= ..."), vec![
+            TokenKind::Int(0),
+            TokenKind::DotDot,
+            TokenKind::Int(1),
+            TokenKind::DotDotEq,
+            TokenKind::Ellipsis,
+        ]);
+    }
+
+    #[test]
+    fn destructuring() {
+        let person = "var person = {\"name\": \"ada\", \"age\": 36, \"langs\": [\"en\", \"fr\"]}\n";
+        let cases = [
+            ("var [a, b] = [1, 2]\na + b".to_string(), "3"),
+            ("var [first, ...rest] = [1, 2, 3]\nrest".to_string(), "[2, 3]"),
+            ("var [_, second, ..._] = [1, 2, 3, 4]\nsecond".to_string(), "2"),
+            ("var [x, ...rest] = [1]\nrest".to_string(), "[]"),
+            ("var [q, r] = (7, 8)\nr".to_string(), "8"),
+            ("var [[a, b], c] = [[1, 2], 3]\na + b + c".to_string(), "6"),
+            (format!("{person}var {{name, age}} = person\n\"${{name}} ${{age}}\""), "ada 36"),
+            (format!("{person}var {{langs: [first, _]}} = person\nfirst"), "en"),
+            (format!("{person}var {{age: years}} = person\nyears"), "36"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+
+        let errors = [
+            ("var [a, b] = [1, 2, 3]".to_string(), "expected 2 elements, found 3 in [1, 2, 3]"),
+            ("var [a, b, ...c] = [1]".to_string(), "expected at least 2 elements, found 1 in [1]"),
+            ("var [a] = 5".to_string(), "expected an array, found 5"),
+            (format!("{person}var {{name, city}} = person"), "key city not found in {name: ada, age: 36, langs: [en, fr]}"),
+            ("var {a} = [1]".to_string(), "expected a hash, found [1]"),
+        ];
+        for (input, expected) in errors {
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+
+        assert_eq!(parse_errors("var [a, +] = x\nvar [...a, b] = x\nvar {1} = x"), vec![
+            "1:9: expected pattern, found `+`",
+            "2:12: the rest element must be the last one",
+            "3:6: expected identifier, found integer `1`",
+        ]);
+    }
+
+    #[test]
+    fn match_expressions() {
+        let describe = "var describe = func(v) {\n  match v {\n    0 => \"zero\"\n    -1 => \"minus one\"\n    1..=9 => \"digit\"\n    \"hi\" => \"greeting\"\n    true => \"yes\"\n    [] => \"empty\"\n    [x] => \"one ${x}\"\n    [first, ...rest] if first > 100 => \"big first, ${len(rest)} more\"\n    [_, ...rest] => \"many, ${len(rest)} more\"\n    {name, age} if age >= 18 => \"adult ${name}\"\n    {name} => \"person ${name}\"\n    n if n < 0 => \"negative\"\n    _ => \"other\"\n  }\n}\n";
+        let cases = [
+            ("0", "zero"),
+            ("-1", "minus one"),
+            ("7", "digit"),
+            ("\"hi\"", "greeting"),
+            ("true", "yes"),
+            ("[]", "empty"),
+            ("[5]", "one 5"),
+            ("[101, 2, 3]", "big first, 2 more"),
+            ("[1, 2, 3]", "many, 2 more"),
+            ("{\"name\": \"ada\", \"age\": 36}", "adult ada"),
+            ("{\"name\": \"bob\", \"age\": 3}", "person bob"),
+            ("-5", "negative"),
+            ("10", "other"),
+        ];
+        for (input, expected) in cases {
+            let input = format!("{describe}describe({input})");
+            assert_eq!(eval(&input).to_string(), expected, "{}", input);
+        }
+
+        let cases = [
+            ("var x = match (1, 2) { [a, b] => a + b }\nx", "3"),
+            ("match 2 { 1 => \"a\", 2 => \"b\", _ => \"c\" }", "b"),
+            ("match 3 { n => { var m = n * 2\n m + 1 } }", "7"),
+            ("var n = 5\nmatch 1 { n => n }\nn", "5"),
+            ("var f = func(x) {\n  match x { 1 => { return \"early\" }, _ => 0 }\n  \"late\"\n}\nf(1)", "early"),
+            ("var s = 0\nfor i in 0..5 { match i { 3 => { break }, _ => { s += i } } }\ns", "3"),
+            ("var f = func(v) {\n  var x = match v { 1 => { return \"early\" }, _ => 0 }\n  \"late\"\n}\nf(1)", "early"),
+            ("var s = 0\nfor i in 0..5 { s += match i { 3 => { break }, _ => i } }\ns", "3"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(eval(input).to_string(), expected, "{}", input);
+        }
+        assert_eq!(eval("match 5 { 1 => 1, 2..4 => 2 }").to_string(), "no match arm matches 5");
+        assert_eq!(eval("match 1 { n if missing => 1 }").to_string(), "missing not found in the current scope");
+
+        let input = "match x {\n  1..10 => 1\n  5 => 2\n  2..=4 => 3\n  [a, ...r] => 4\n  [1, 2] => 5\n  {a} if a => 6\n  {a, b} => 7\n  n => 8\n  _ => 9\n}";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program().unwrap();
+        let warnings: Vec<String> = parser.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings, vec![
+            "3:3: unreachable match arm, the arm at 2:3 already matches every value it does",
+            "4:3: unreachable match arm, the arm at 2:3 already matches every value it does",
+            "6:3: unreachable match arm, the arm at 5:3 already matches every value it does",
+            "10:3: unreachable match arm, the arm at 9:3 already matches every value it does",
+        ]);
+
+        let mut parser = Parser::new(Lexer::new("match 1 { 1.0 => \"f\", 1 => \"i\" }".to_string()));
+        parser.parse_program().unwrap();
+        let warnings: Vec<String> = parser.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings, vec!["1:23: unreachable match arm, the arm at 1:11 already matches every value it does"]);
+
+        assert_eq!(parse_errors("match x { 1 2 }\nmatch x { \"a\"..2 => 1 }\nmatch x { 1 => 1"), vec![
+            "1:13: expected `=>`, found integer `2`",
+            "2:16: range patterns need integer bounds",
+            "3:17: expected newline, `,` or `}`, found end of file",
+        ]);
+        assert_eq!(parse_errors("match x { 1..2..3 => 1 }"), vec!["1:15: range patterns can't be chained"]);
+        let chain = format!("match 1 {{ {}1 => 1 }}", "1..".repeat(100_000));
+        assert_eq!(parse_errors(&chain), vec!["1:15: range patterns can't be chained"]);
+        assert_eq!(eval("match -3 { -5..-2 => 1, _ => 2 }").to_string(), "1");
+    }
 }
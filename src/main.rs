@@ -31,7 +31,19 @@ fn main() {
     let lexer = Lexer::new(input);
         
     let mut parser = Parser::new(lexer);
-    let stmt = parser.parse_program();
+    let program = parser.parse_program();
+    for warning in parser.warnings() {
+        println!("Warning {}", warning);
+    }
+    let stmt = match program {
+        Ok(stmt) => stmt,
+        Err(errors) => {
+            for err in errors {
+                println!("Error {}", err);
+            }
+            return
+        }
+    };
     let env = Env::new();
     let mut eval = Eval::new(Rc::new(RefCell::new(env)));
     eval.eval_program(stmt);
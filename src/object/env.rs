@@ -2,9 +2,9 @@ use std::{collections::HashMap, cell::RefCell , rc::Rc};
 
 use super::object::Object;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Env {
-    map: HashMap<String, Object>,
+    map: HashMap<Rc<str>, Object>,
     outer: Option<Rc<RefCell<Env>>>,
 }
 impl Env {
@@ -21,7 +21,7 @@ impl Env {
         }
     }
     pub fn read_ident(&self, s: &str) -> Option<Object> {
-        match self.map.get(&s.to_string()) {
+        match self.map.get(s) {
             Some(obj) => {
                 Some(obj.clone())
             }
@@ -37,7 +37,23 @@ impl Env {
             }
         }
     }
-    pub fn add_ident(&mut self, val: Object, key: String) {
+    pub fn add_ident(&mut self, val: Object, key: Rc<str>) {
         self.map.insert(key, val);
     }
+    /// Updates the binding in the scope that declared it. Returns false
+    /// if no scope did.
+    pub fn assign(&mut self, key: &str, val: Object) -> bool {
+        if let Some(obj) = self.map.get_mut(key) {
+            *obj = val;
+            return true
+        }
+        match self.outer {
+            Some(ref outer) => {
+                outer.borrow_mut().assign(key, val)
+            }
+            None => {
+                false
+            }
+        }
+    }
 }
@@ -0,0 +1,106 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use super::object::Object;
+
+/// The values that can be used as keys of a hash.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum HashKey {
+    Int(i64),
+    String(String),
+    Bool(bool),
+}
+
+impl HashKey {
+    pub fn from_object(obj: &Object) -> Option<HashKey> {
+        match obj {
+            Object::Int(i) => Some(HashKey::Int(*i)),
+            Object::String(s) => Some(HashKey::String(s.clone())),
+            Object::Bool(b) => Some(HashKey::Bool(*b)),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            HashKey::Int(i) => Object::Int(*i),
+            HashKey::String(s) => Object::String(s.clone()),
+            HashKey::Bool(b) => Object::Bool(*b),
+        }
+    }
+}
+
+impl fmt::Display for HashKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_object())
+    }
+}
+
+/// A map that keeps its entries in insertion order.
+#[derive(Clone, Default)]
+pub struct OrderedMap {
+    entries: Vec<(HashKey, Object)>,
+    // position of each key in `entries`
+    index: HashMap<HashKey, usize>,
+}
+
+impl OrderedMap {
+    pub fn new() -> Self {
+        return OrderedMap {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Sets the value of `key`. A key that is already present keeps its place.
+    pub fn insert(&mut self, key: HashKey, val: Object) {
+        match self.index.get(&key) {
+            Some(&i) => {
+                self.entries[i].1 = val;
+            }
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, val));
+            }
+        }
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Object> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &HashKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: &HashKey) -> Option<Object> {
+        let i = self.index.remove(key)?;
+        let (_, val) = self.entries.remove(i);
+        for pos in self.index.values_mut() {
+            if *pos > i {
+                *pos -= 1;
+            }
+        }
+        Some(val)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(HashKey, Object)> {
+        self.entries.iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &HashKey> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Object> {
+        self.entries.iter().map(|(_, val)| val)
+    }
+}
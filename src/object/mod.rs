@@ -1,2 +1,3 @@
 pub mod object;
 pub mod env;
+pub mod hash;
@@ -1,9 +1,10 @@
 use core::fmt;
 use std::{rc::Rc, cell::RefCell};
 
-use crate::ast::ast::{BlockStmt, Ident};
+use crate::ast::ast::{BlockStmt, Literals, Param};
 
 use super::env::Env;
+use super::hash::{HashKey, OrderedMap};
 
 pub type BuiltinFunction = fn(Vec<Object>) -> Object;
 
@@ -14,9 +15,16 @@ pub enum Object {
     Float(f64),
     Bool(bool),
     Arr(Vec<Object>),
+    Tuple(Vec<Object>),
+    Hash(OrderedMap),
+    // integers from the start up to, but not including, the end
+    Range(i64, i64),
     Return(Box<Object>),
+    // leave or restart the loop with the given label, or the innermost one
+    Break(Option<Rc<str>>),
+    Continue(Option<Rc<str>>),
 
-    Function(Vec<Ident>, BlockStmt, Rc<RefCell<Env>>),
+    Function(Vec<Param>, BlockStmt, Rc<RefCell<Env>>),
     Builtin(i64, BuiltinFunction),
 
     Error(String),
@@ -50,9 +58,84 @@ impl fmt::Display for Object {
                     .to_string();
                 write!(f, "[{}]", objs)
             }
+            Object::Error(err) => {
+                write!(f, "{}", err)
+            }
+            Object::Hash(map) => {
+                let entries = map
+                    .iter()
+                    .map(|(key, val)| format!("{}: {}", key, val))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "{{{}}}", entries)
+            }
+            Object::Range(start, end) => {
+                write!(f, "{}..{}", start, end)
+            }
+            Object::Tuple(items) => {
+                let objs = items
+                    .iter()
+                    .map(|obj| format!("{}", obj))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                if items.len() == 1 {
+                    write!(f, "({},)", objs)
+                } else {
+                    write!(f, "({})", objs)
+                }
+            }
             _ =>{
                 write!(f, "")
             }
         }
     }
 }
+
+impl Object {
+    /// The value of a literal pattern such as `1` or `"a"`.
+    pub fn from_literal(literal: &Literals) -> Object {
+        match literal {
+            Literals::Int(i) => Object::Int(*i),
+            Literals::Float(f) => Object::Float(*f),
+            Literals::Bool(b) => Object::Bool(*b),
+            Literals::String(s) => Object::String(s.clone()),
+            _ => Object::None,
+        }
+    }
+
+    /// Whether this is a `return`, `break`, `continue` or an error. These
+    /// stop evaluation and are passed on instead of being used as values.
+    pub fn is_signal(&self) -> bool {
+        matches!(self, Object::Return(_) | Object::Break(_) | Object::Continue(_) | Object::Error(_))
+    }
+
+    /// Structural equality as used by `==`. Ints and floats compare by value,
+    /// functions are never equal.
+    pub fn equals(&self, other: &Object) -> bool {
+        match (self, other) {
+            (Object::Int(l), Object::Int(r)) => l == r,
+            (Object::Float(l), Object::Float(r)) => l == r,
+            (Object::Int(l), Object::Float(r)) | (Object::Float(r), Object::Int(l)) => *l as f64 == *r,
+            (Object::String(l), Object::String(r)) => l == r,
+            (Object::Bool(l), Object::Bool(r)) => l == r,
+            (Object::Arr(l), Object::Arr(r)) | (Object::Tuple(l), Object::Tuple(r)) => {
+                l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.equals(r))
+            }
+            (Object::Range(l1, l2), Object::Range(r1, r2)) => l1 == r1 && l2 == r2,
+            // the order of the entries doesn't matter
+            (Object::Hash(l), Object::Hash(r)) => {
+                l.len() == r.len() && l.iter().all(|(key, val)| r.get(key).is_some_and(|other| val.equals(other)))
+            }
+            (Object::None, Object::None) => true,
+            _ => false,
+        }
+    }
+
+    /// The key for this value in a hash, or an error if it can't be one.
+    pub fn hash_key(&self) -> Result<HashKey, Object> {
+        match HashKey::from_object(self) {
+            Some(key) => Ok(key),
+            None => Err(Object::Error(format!("{} can't be used as a hash key", self))),
+        }
+    }
+}
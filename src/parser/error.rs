@@ -0,0 +1,49 @@
+use std::fmt;
+
+use crate::token::token::{Span, TokenKind};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    /// Descriptions of what would have been accepted, e.g. "`)`" or "expression".
+    pub expected: Vec<String>,
+    pub found: TokenKind,
+    pub span: Span,
+    /// Explanation for errors that are not about a missing token.
+    pub message: Option<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(msg) = &self.message {
+            return write!(f, "{}: {}", self.span, msg)
+        }
+        if let TokenKind::Error(msg) = &self.found {
+            return write!(f, "{}: {}", self.span, msg)
+        }
+
+        match self.expected.split_last() {
+            Some((last, [])) => {
+                write!(f, "{}: expected {}, found {}", self.span, last, self.found)
+            }
+            Some((last, rest)) => {
+                write!(f, "{}: expected {} or {}, found {}", self.span, rest.join(", "), last, self.found)
+            }
+            None => {
+                write!(f, "{}: unexpected {}", self.span, self.found)
+            }
+        }
+    }
+}
+
+/// Something suspicious that doesn't stop the program from running.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Warning {
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
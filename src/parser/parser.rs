@@ -1,20 +1,54 @@
 use crate::lexer::lexer::Lexer;
-use crate::token::token::Token;
+use crate::token::token::{Span, Token, TokenKind};
 use crate::ast::ast::*;
+use crate::object::object::Object;
+
+use super::error::{ParseError, Warning};
+
+/// How deeply expressions may nest before parsing stops with an error.
+/// Keeps the recursive descent well inside the default thread stack.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
 
 pub struct Parser {
     cur_token: Token,
     next_token: Token,
     lexer: Lexer,
+    errors: Vec<ParseError>,
+    warnings: Vec<Warning>,
+    depth: usize,
+    max_depth: usize,
+    // number of enclosing `(`, `[` or hash literals, inside which newlines are skipped
+    groups: usize,
+    // number of `{` read and not yet closed
+    braces: usize,
+    // number of `(` and `[` read and not yet closed
+    brackets: usize,
+    // set in a match guard, where `=>` ends the guard instead of making
+    // the expression before it an arrow function
+    in_guard: bool,
+    // labels of the enclosing loops of the current function
+    loops: Vec<Option<Ident>>,
 }
 
 impl Parser {
     pub fn new(l: Lexer) -> Self{
+        Parser::new_with_max_depth(l, DEFAULT_MAX_DEPTH)
+    }
 
+    pub fn new_with_max_depth(l: Lexer, max_depth: usize) -> Self {
         let mut parser = Parser{
-            cur_token: Token::None,
-            next_token: Token::None,
+            cur_token: Token::new(TokenKind::None, Span::default()),
+            next_token: Token::new(TokenKind::None, Span::default()),
             lexer: l,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            depth: 0,
+            max_depth,
+            groups: 0,
+            braces: 0,
+            brackets: 0,
+            in_guard: false,
+            loops: Vec::new(),
         };
         parser.next();
         parser.next();
@@ -22,113 +56,493 @@ impl Parser {
         parser
     }
 
-    pub fn parse_program(&mut self) -> Program{
+    /// Parses the whole input. Statements end at a newline, a `;`, a closing
+    /// brace or the end of the input. A statement with an error is skipped up
+    /// to the next of these, so every error is reported at once.
+    pub fn parse_program(&mut self) -> Result<Program, Vec<ParseError>> {
         let mut parsed: Vec<Statement> = Vec::new();
 
-        while self.cur_token != Token::EOF {
-            let stmt = self.parse_statement();        
-            parsed.push(stmt);
+        while self.cur_token.kind != TokenKind::EOF {
+            if matches!(self.cur_token.kind, TokenKind::NewLine | TokenKind::Semicolon | TokenKind::DocComment(_)) {
+                self.next();
+                continue;
+            }
+            if let Some(stmt) = self.parse_statement_or_sync() {
+                parsed.push(stmt);
+            }
             self.next();
         }
 
-        parsed
+        if self.errors.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Warnings found while parsing, such as match arms that can't be reached.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
     }
+
     fn parse_block(&mut self) -> BlockStmt {
-        let mut parsed = Vec::new();
+        // newlines end statements again inside a block, even within parens
+        let groups = std::mem::take(&mut self.groups);
+        let block = self.parse_block_stmts();
+        self.groups = groups;
+        self.skip_group_newlines();
 
-        if self.cur_token != Token::LBRACE {
-            return parsed
-        }
+        block
+    }
+
+    fn parse_block_stmts(&mut self) -> BlockStmt {
+        let mut parsed = Vec::new();
 
         self.next();
-        while self.cur_token != Token::RBRACE {
-            parsed.push(self.parse_statement()); 
+        while self.cur_token.kind != TokenKind::RBRACE {
+            match self.cur_token.kind {
+                TokenKind::NewLine | TokenKind::Semicolon | TokenKind::DocComment(_) => {
+                    self.next();
+                    continue;
+                }
+                TokenKind::EOF => {
+                    self.error_at_cur(vec![TokenKind::RBRACE.to_string()]);
+                    return parsed
+                }
+                _ => {}
+            }
+            if let Some(stmt) = self.parse_statement_or_sync() {
+                parsed.push(stmt);
+            }
             self.next();
         }
 
         parsed
     }
 
+    fn parse_statement_or_sync(&mut self) -> Option<Statement> {
+        let errors = self.errors.len();
+        // the depth around the statement, without a `{`, `(` or `[` it starts with
+        let braces = self.braces - (self.cur_token.kind == TokenKind::LBRACE) as usize;
+        let brackets = self.brackets - matches!(self.cur_token.kind, TokenKind::LPAREN | TokenKind::LBRACKET) as usize;
+        let stmt = self.parse_statement();
+        if self.errors.len() == errors && !Self::ends_statement(&self.next_token.kind) {
+            self.error_at_next(vec![TokenKind::NewLine.to_string(), TokenKind::Semicolon.to_string()]);
+        }
+        if self.errors.len() > errors {
+            self.synchronize(braces, brackets);
+            return None
+        }
+        Some(stmt)
+    }
+
+    fn ends_statement(token: &TokenKind) -> bool {
+        matches!(
+            token,
+            TokenKind::NewLine | TokenKind::Semicolon | TokenKind::DocComment(_) | TokenKind::RBRACE | TokenKind::EOF
+        )
+    }
+
+    /// Skips to the last token before the next newline, `;` or closing brace
+    /// outside of any braces or brackets opened since the statement started
+    /// at `braces` and `brackets`. A `(` or `[` the error left open ends at
+    /// the closing brace of the block, or at a line starting with a keyword
+    /// that only begins a statement.
+    fn synchronize(&mut self, braces: usize, brackets: usize) {
+        loop {
+            match self.next_token.kind {
+                TokenKind::EOF => {
+                    return
+                }
+                TokenKind::NewLine | TokenKind::Semicolon if self.braces <= braces && self.brackets <= brackets => {
+                    return
+                }
+                TokenKind::RBRACE if self.braces <= braces => {
+                    self.brackets = brackets;
+                    return
+                }
+                TokenKind::Var | TokenKind::Return | TokenKind::While | TokenKind::For | TokenKind::Break | TokenKind::Continue
+                    if self.cur_token.kind == TokenKind::NewLine && self.braces <= braces =>
+                {
+                    self.brackets = brackets;
+                    return
+                }
+                _ => {}
+            }
+            self.next();
+        }
+    }
+
     fn parse_statement(&mut self) -> Statement {
-        return match self.cur_token {
-            Token::Var => {
+        return match self.cur_token.kind {
+            TokenKind::Var => {
                 self.parse_var()
             }
-            Token::Return => {
+            TokenKind::Return => {
                 self.next();
                 Statement::Return(self.parse_expression(Precedences::Lowest))
             }
-            _ => {
-                Statement::ExpressionStmt(self.parse_expression(Precedences::Lowest))
+            TokenKind::While | TokenKind::For => {
+                self.parse_loop(None)
             }
-        }
-    }
-    fn parse_var(&mut self) -> Statement {
-        let token = self.next_token.clone();
-        return match  token {
-            Token::Ident(s) => {
-                let ident = s;
+            TokenKind::Ident(ref i) if self.next_token.kind == TokenKind::Colon => {
+                let label = Ident { literal: i.clone() };
+                self.next();
                 self.next();
-                if self.next_token != Token::Assign {
+                if !matches!(self.cur_token.kind, TokenKind::While | TokenKind::For) {
+                    self.error_at_cur(vec![TokenKind::While.to_string(), TokenKind::For.to_string()]);
                     return Statement::None
                 }
-                self.next();
-                self.next();
-                let val = self.parse_expression(Precedences::Lowest);
-                
-                Statement::Var(Ident{literal: ident.to_string()}, val)
+                self.parse_loop(Some(label))
+            }
+            TokenKind::Break | TokenKind::Continue => {
+                self.parse_loop_jump()
             }
             _ => {
-            Statement::None
+                let span = self.cur_token.span;
+                let exp = self.parse_expression(Precedences::Lowest);
+                match Self::token_to_assign(&self.next_token.kind) {
+                    Some(op) if !matches!(exp, Expression::None) => {
+                        self.parse_assign(span, exp, op)
+                    }
+                    _ => {
+                        Statement::ExpressionStmt(exp)
+                    }
+                }
+            }
+        }
+    }
+    /// Parses `while (cond) { ... }` or `for x in xs { ... }`.
+    fn parse_loop(&mut self, label: Option<Ident>) -> Statement {
+        let is_while = self.cur_token.kind == TokenKind::While;
+        let mut ident = None;
+        if is_while {
+            if !self.expect_next(TokenKind::LPAREN) {
+                return Statement::None
+            }
+        } else {
+            ident = self.expect_ident();
+            if ident.is_none() || !self.expect_next(TokenKind::In) {
+                return Statement::None
+            }
+        }
+        let exp = self.in_group(|parser| {
+            parser.next();
+            parser.parse_expression(Precedences::Lowest)
+        });
+        if matches!(exp, Expression::None) {
+            return Statement::None
+        }
+        if is_while && !self.expect_next(TokenKind::RPAREN) {
+            return Statement::None
+        }
+        if !self.expect_next(TokenKind::LBRACE) {
+            return Statement::None
+        }
+
+        // loop bodies nest without passing through `parse_expression`
+        if self.depth >= self.max_depth {
+            self.error_message(self.cur_token.span, format!("loop is nested more than {} levels deep", self.max_depth));
+            return Statement::None
+        }
+        self.depth += 1;
+        self.loops.push(label.clone());
+        let body = self.parse_block();
+        self.loops.pop();
+        self.depth -= 1;
 
+        match ident {
+            Some(ident) => Statement::For(label, ident, exp, body),
+            None => Statement::While(label, exp, body),
+        }
+    }
+
+    /// Parses `break` or `continue` with an optional label.
+    fn parse_loop_jump(&mut self) -> Statement {
+        let keyword = self.cur_token.clone();
+        let label = match &self.next_token.kind {
+            TokenKind::Ident(i) => {
+                let label = Ident { literal: i.clone() };
+                self.next();
+                Some(label)
             }
+            _ => None,
+        };
+
+        if self.loops.is_empty() {
+            self.error_message(keyword.span, format!("{} outside of a loop", keyword.kind));
+        } else if label.is_some() && !self.loops.contains(&label) {
+            self.error_message(self.cur_token.span, format!("unknown loop label `{}`", label.as_ref().unwrap()));
         }
 
+        if keyword.kind == TokenKind::Break {
+            Statement::Break(label)
+        } else {
+            Statement::Continue(label)
+        }
     }
 
-    fn parse_expression(&mut self, precedence: Precedences) -> Expression {
-        let mut left = self.parse_prefix();
-        if left == Expression::None  {
-            match &self.cur_token {
-                Token::Int(i) => { left = Expression::Literal(Literals::Int(*i)) }
-                Token::Bool(b) => { left = Expression::Literal(Literals::Bool(*b)) }
-                Token::String(s) => { left = Expression::Literal(Literals::String(s.clone())) }
-                Token::Ident(i) => { left = Expression::Ident(Ident{literal: i.clone()}) }
-                _ => { }
+    /// Parses the value of `target = value` from the `=`, or from an
+    /// operator like `+=`.
+    fn parse_assign(&mut self, span: Span, target: Expression, op: Option<Infix>) -> Statement {
+        if !matches!(target, Expression::Ident(_) | Expression::Index(..)) {
+            self.error_message(span, "only names and indexes can be assigned to".to_string());
+            return Statement::None
+        }
+        self.next();
+        self.next();
+        let val = self.parse_expression(Precedences::Lowest);
+
+        Statement::Assign(target, op, val)
+    }
+
+    fn token_to_assign(token: &TokenKind) -> Option<Option<Infix>> {
+        let op = match token {
+            TokenKind::Assign => None,
+            TokenKind::PlusAssign => Some(Infix::Plus),
+            TokenKind::MinusAssign => Some(Infix::Minus),
+            TokenKind::StarAssign => Some(Infix::Star),
+            TokenKind::SlashAssign => Some(Infix::Slash),
+            TokenKind::PercentAssign => Some(Infix::Percent),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn parse_var(&mut self) -> Statement {
+        let mut pattern = None;
+        let mut ident = None;
+        if matches!(self.next_token.kind, TokenKind::LBRACKET | TokenKind::LBRACE) {
+            self.next();
+            pattern = self.parse_pattern();
+            if pattern.is_none() {
+                return Statement::None
             }
+        } else {
+            ident = self.expect_ident();
+            if ident.is_none() {
+                return Statement::None
+            }
+        }
+        if !self.expect_next(TokenKind::Assign) {
+            return Statement::None
+        }
+        self.next();
+        let val = self.parse_expression(Precedences::Lowest);
+
+        match (pattern, ident) {
+            (Some(pattern), _) => Statement::VarDestructure(pattern, val),
+            (None, Some(ident)) => Statement::Var(ident, val),
+            (None, None) => Statement::None,
+        }
+    }
+
+    /// Parses a pattern starting at its first token.
+    fn parse_pattern(&mut self) -> Option<Pattern> {
+        if self.depth >= self.max_depth {
+            self.error_message(self.cur_token.span, format!("pattern is nested more than {} levels deep", self.max_depth));
+            return None
         }
+        self.depth += 1;
+
+        let pattern = match &self.cur_token.kind {
+            TokenKind::Ident(i) if &**i == "_" => {
+                Some(Pattern::Wildcard)
+            }
+            TokenKind::Ident(i) => {
+                Some(Pattern::Ident(Ident { literal: i.clone() }))
+            }
+            TokenKind::LBRACKET => {
+                self.parse_array_pattern()
+            }
+            TokenKind::LBRACE => {
+                self.parse_hash_pattern()
+            }
+            _ => {
+                self.parse_literal_pattern()
+            }
+        };
+
+        self.depth -= 1;
+        pattern
+    }
 
-        while precedence < Self::token_to_precedence(self.next_token.clone()) {
+    /// Parses a literal like `-1` or `"a"`, or a range like `1..=5`.
+    fn parse_literal_pattern(&mut self) -> Option<Pattern> {
+        let negative = self.cur_token.kind == TokenKind::Minus;
+        if negative {
             self.next();
-            if self.cur_token == Token::EOF || self.cur_token == Token::EOF{
-                break;
+        }
+        let literal = match &self.cur_token.kind {
+            TokenKind::Int(i) if negative => Literals::Int(i.wrapping_neg()),
+            TokenKind::Float(f) if negative => Literals::Float(-f),
+            TokenKind::Int(i) => Literals::Int(*i),
+            TokenKind::Float(f) => Literals::Float(*f),
+            TokenKind::String(s) if !negative => Literals::String(s.clone()),
+            TokenKind::Bool(b) if !negative => Literals::Bool(*b),
+            _ => {
+                self.error_at_cur(vec!["pattern".to_string()]);
+                return None
+            }
+        };
+
+        let inclusive = match self.next_token.kind {
+            TokenKind::DotDot => false,
+            TokenKind::DotDotEq => true,
+            _ => return Some(Pattern::Literal(literal)),
+        };
+        self.next();
+        self.next();
+        let span = self.cur_token.span;
+        let start = match literal {
+            Literals::Int(start) => Some(start),
+            _ => None,
+        };
+        // the end is a single literal, so `1..2..3` is an error
+        let negative = self.cur_token.kind == TokenKind::Minus;
+        if negative {
+            self.next();
+        }
+        let end = match &self.cur_token.kind {
+            TokenKind::Int(i) if negative => Some(i.wrapping_neg()),
+            TokenKind::Int(i) => Some(*i),
+            TokenKind::Float(_) => None,
+            TokenKind::String(_) | TokenKind::Bool(_) if !negative => None,
+            _ => {
+                self.error_at_cur(vec!["pattern".to_string()]);
+                return None
+            }
+        };
+        let end = if inclusive { end.and_then(|end| end.checked_add(1)) } else { end };
+        if matches!(self.next_token.kind, TokenKind::DotDot | TokenKind::DotDotEq) {
+            self.error_message(self.next_token.span, "range patterns can't be chained".to_string());
+            return None
+        }
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                Some(Pattern::Range(start, end))
+            }
+            _ => {
+                self.error_message(span, "range patterns need integer bounds".to_string());
+                None
+            }
+        }
+    }
+
+    /// Parses `[a, b, ...rest]` from the `[`.
+    fn parse_array_pattern(&mut self) -> Option<Pattern> {
+        let (items, rest) = self.in_group(|parser| {
+            let mut items = Vec::new();
+            let mut rest = None;
+            while parser.next_token.kind != TokenKind::RBRACKET {
+                parser.next();
+                if parser.cur_token.kind == TokenKind::Ellipsis {
+                    parser.expect_ident()?;
+                    rest = Some(Box::new(parser.parse_pattern()?));
+                    // nothing may follow the rest, but a trailing comma
+                    if parser.next_token.kind == TokenKind::Comma {
+                        parser.next();
+                    }
+                    if parser.next_token.kind != TokenKind::RBRACKET {
+                        parser.error_message(parser.next_token.span, "the rest element must be the last one".to_string());
+                        return None
+                    }
+                    break;
+                }
+                items.push(parser.parse_pattern()?);
+                if parser.next_token.kind != TokenKind::Comma {
+                    break;
+                }
+                parser.next();
             }
+            Some((items, rest))
+        })?;
 
+        if !self.expect_closing(TokenKind::RBRACKET) {
+            return None
+        }
+        Some(Pattern::Array(items, rest))
+    }
+
+    /// Parses `{name, age: pattern}` from the `{`.
+    fn parse_hash_pattern(&mut self) -> Option<Pattern> {
+        let entries = self.in_group(|parser| {
+            let mut entries = Vec::new();
+            while parser.next_token.kind != TokenKind::RBRACE {
+                let key = parser.expect_ident()?;
+                let pattern = if parser.next_token.kind == TokenKind::Colon {
+                    parser.next();
+                    parser.next();
+                    parser.parse_pattern()?
+                } else {
+                    Pattern::Ident(key.clone())
+                };
+                entries.push((key.literal.to_string(), pattern));
+                if parser.next_token.kind != TokenKind::Comma {
+                    break;
+                }
+                parser.next();
+            }
+            Some(entries)
+        })?;
+
+        if !self.expect_closing(TokenKind::RBRACE) {
+            return None
+        }
+        Some(Pattern::Hash(entries))
+    }
+
+    fn parse_expression(&mut self, precedence: Precedences) -> Expression {
+        // every nested expression passes through here, so this bounds their
+        // recursion; loops and patterns check the depth themselves
+        if self.depth >= self.max_depth {
+            self.error_message(self.cur_token.span, format!("expression is nested more than {} levels deep", self.max_depth));
+            return Expression::None
+        }
+        self.depth += 1;
+
+        let mut left = self.parse_prefix();
+
+        while !matches!(left, Expression::None) && precedence < Self::token_to_precedence(&self.next_token.kind) {
+            self.next();
             left = self.parse_infix(left);
         }
-            
+
+        self.depth -= 1;
         left
     }
 
 
-    fn token_to_precedence(token: Token) -> Precedences {
+    fn token_to_precedence(token: &TokenKind) -> Precedences {
         return match token {
-            Token::Minus | Token::Plus => {
+            TokenKind::Minus | TokenKind::Plus => {
                 Precedences::Sum
             }
-            Token::Star | Token::Slash => {
+            TokenKind::Star | TokenKind::Slash | TokenKind::Percent => {
                 Precedences::Product
             }
-            Token::EQ | Token::NotEQ => {
+            TokenKind::EQ | TokenKind::NotEQ => {
                 Precedences::Equals
             }
-            Token::LT | Token::GT => {
+            TokenKind::LT | TokenKind::GT | TokenKind::LTE | TokenKind::GTE => {
                 Precedences::LessGreater
             }
-            Token::LPAREN => {
+            TokenKind::And => {
+                Precedences::And
+            }
+            TokenKind::Or => {
+                Precedences::Or
+            }
+            TokenKind::Pipe => {
+                Precedences::Pipe
+            }
+            TokenKind::DotDot | TokenKind::DotDotEq => {
+                Precedences::Range
+            }
+            TokenKind::LPAREN => {
                 Precedences::Call
             }
-            Token::LBRACKET => {
+            TokenKind::LBRACKET | TokenKind::Dot => {
                 Precedences::Index
             }
             _ => {
@@ -137,203 +551,754 @@ impl Parser {
         };
     }
 
-    
+
     fn next(&mut self) {
-        self.cur_token = self.next_token.clone();
-        self.next_token = self.lexer.next_token();
+        self.cur_token = std::mem::replace(&mut self.next_token, self.lexer.next_token());
+        match self.cur_token.kind {
+            TokenKind::LBRACE => {
+                self.braces += 1;
+            }
+            TokenKind::RBRACE => {
+                self.braces = self.braces.saturating_sub(1);
+            }
+            TokenKind::LPAREN | TokenKind::LBRACKET => {
+                self.brackets += 1;
+            }
+            TokenKind::RPAREN | TokenKind::RBRACKET => {
+                self.brackets = self.brackets.saturating_sub(1);
+            }
+            _ => {}
+        }
+        self.skip_group_newlines();
     }
 
-    fn parse_prefix(&mut self) -> Expression {
+    fn skip_group_newlines(&mut self) {
+        while self.groups > 0 && matches!(self.next_token.kind, TokenKind::NewLine | TokenKind::DocComment(_)) {
+            self.next_token = self.lexer.next_token();
+        }
+    }
 
-        match self.cur_token {
-            Token::If => {
-                self.next();
-                if self.cur_token != Token::LPAREN {
-                    return Expression::None
-                }
-                self.next();
-                let cond = self.parse_expression(Precedences::Lowest);
+    /// Skips newlines after the current token, e.g. after a binary operator.
+    fn skip_newlines(&mut self) {
+        while matches!(self.next_token.kind, TokenKind::NewLine | TokenKind::DocComment(_)) {
+            self.next();
+        }
+    }
 
-                if self.next_token != Token::RPAREN {
-                    return Expression::None
-                }
-                self.next();
+    /// Runs `parse` with newlines skipped, starting at an opening `(`, `[` or
+    /// the `{` of a hash literal.
+    /// `parse` should stop with the closing token as the next token, so the
+    /// token after it is read with the outer rules again.
+    fn in_group<T>(&mut self, parse: impl FnOnce(&mut Self) -> T) -> T {
+        self.groups += 1;
+        self.skip_group_newlines();
+        let in_guard = std::mem::replace(&mut self.in_guard, false);
+        let parsed = parse(self);
+        self.in_guard = in_guard;
+        self.groups -= 1;
 
-                self.next();
-                let if_block = self.parse_block();
-                self.next();
+        parsed
+    }
 
-                let else_block;
-                if self.cur_token == Token::Else {
-                    self.next();
-                    else_block = Some(self.parse_block());
-                } else {
-                    else_block = None;
-                }
+    /// Moves to the next token if it is `kind`, otherwise records an error.
+    fn expect_next(&mut self, kind: TokenKind) -> bool {
+        if self.next_token.kind == kind {
+            self.next();
+            return true
+        }
+        self.error_at_next(vec![kind.to_string()]);
+        false
+    }
 
-                Expression::If(
-                    Box::new(cond),
-                    if_block,
-                    else_block
-                )
+    fn expect_ident(&mut self) -> Option<Ident> {
+        if let TokenKind::Ident(i) = &self.next_token.kind {
+            let ident = Ident { literal: i.clone() };
+            self.next();
+            return Some(ident)
+        }
+        self.error_at_next(vec!["identifier".to_string()]);
+        None
+    }
+
+    fn error_at_cur(&mut self, expected: Vec<String>) {
+        self.push_error(ParseError {
+            expected,
+            found: self.cur_token.kind.clone(),
+            span: self.cur_token.span,
+            message: None,
+        });
+    }
+
+    fn error_at_next(&mut self, expected: Vec<String>) {
+        self.push_error(ParseError {
+            expected,
+            found: self.next_token.kind.clone(),
+            span: self.next_token.span,
+            message: None,
+        });
+    }
+
+    /// Records an error with its own message instead of the expected tokens.
+    fn error_message(&mut self, span: Span, message: String) {
+        self.push_error(ParseError {
+            expected: Vec::new(),
+            found: self.cur_token.kind.clone(),
+            span,
+            message: Some(message),
+        });
+    }
+
+    /// Records an error unless one was already reported at the same place,
+    /// e.g. by every unclosed block at the end of the input.
+    fn push_error(&mut self, err: ParseError) {
+        if let Some(last) = self.errors.last() {
+            if last.span == err.span {
+                return
             }
-            Token::Func => {
-                self.next();
-                let params = self.parse_function_params();
+        }
+        self.errors.push(err);
+    }
 
-                self.next();
+    fn parse_prefix(&mut self) -> Expression {
 
+        match &self.cur_token.kind {
+            TokenKind::Int(i) => {
+                Expression::Literal(Literals::Int(*i))
+            }
+            TokenKind::Float(f) => {
+                Expression::Literal(Literals::Float(*f))
+            }
+            TokenKind::Bool(b) => {
+                Expression::Literal(Literals::Bool(*b))
+            }
+            TokenKind::String(s) => {
+                Expression::Literal(Literals::String(s.clone()))
+            }
+            TokenKind::Ident(i) => {
+                let ident = Ident { literal: i.clone() };
+                if self.next_token.kind == TokenKind::Arrow && !self.in_guard {
+                    self.next();
+                    return self.parse_arrow_body(vec![ident])
+                }
+                Expression::Ident(ident)
+            }
+            TokenKind::If => {
+                self.parse_if()
+            }
+            TokenKind::Match => {
+                self.parse_match()
+            }
+            TokenKind::Func => {
+                if !self.expect_next(TokenKind::LPAREN) {
+                    return Expression::None
+                }
+                let params = match self.parse_function_params() {
+                    Some(params) => params,
+                    None => return Expression::None,
+                };
+                if !self.expect_next(TokenKind::LBRACE) {
+                    return Expression::None
+                }
+
+                // loops outside of the function can't be left from inside it
+                let loops = std::mem::take(&mut self.loops);
                 let body = self.parse_block();
+                self.loops = loops;
 
                 Expression::Function(
-                    params, 
+                    params,
                     body,
                 )
             }
 
-            Token::LBRACKET => {
-                self.next();
-                println!("bracket");
-                let mut exps = Vec::new();
-                while self.cur_token != Token::RBRACKET {
-                    exps.push(self.parse_expression(Precedences::Lowest));
-                    self.next();
-                    if self.cur_token == Token::Comma {
-                        self.next();
+            TokenKind::LBRACKET => {
+                match self.parse_expression_list(TokenKind::RBRACKET) {
+                    Some(exps) => {
+                        Expression::Literal(Literals::Arr(exps))
+                    }
+                    None => {
+                        Expression::None
                     }
                 }
-
-                Expression::Literal(Literals::Arr(exps))
             }
 
-            Token::Minus => {
+            TokenKind::LPAREN => {
+                self.parse_group()
+            }
+            TokenKind::LBRACE => {
+                self.parse_hash()
+            }
+            TokenKind::Minus => {
                 self.next();
                 let exp =  self.parse_expression(Precedences::Prefix);
                 Expression::Prefix(Prefix::Minus, Box::new(exp))
             }
+            TokenKind::TemplateStart(_) => {
+                self.parse_template()
+            }
+            TokenKind::Bang => {
+                self.next();
+                let exp =  self.parse_expression(Precedences::Prefix);
+                Expression::Prefix(Prefix::Bang, Box::new(exp))
+            }
+            TokenKind::Error(_) => {
+                self.error_at_cur(Vec::new());
+                Expression::None
+            }
             _ => {
+                self.error_at_cur(vec!["expression".to_string()]);
                 Expression::None
             }
         }
     }
 
-    fn parse_infix(&mut self, exp: Expression) -> Expression {
-        return match self.cur_token {
-            Token::LPAREN => {
-                let args = self.parse_expression_list();
+    /// Parses an `if` with its `else if` branches, in a loop so a long
+    /// chain doesn't nest.
+    fn parse_if(&mut self) -> Expression {
+        let mut branches = Vec::new();
+        loop {
+            if !self.expect_next(TokenKind::LPAREN) {
+                return Expression::None
+            }
+            let cond = self.in_group(|parser| {
+                parser.next();
+                parser.parse_expression(Precedences::Lowest)
+            });
 
-                Expression::FunctionCall(args, Box::new(exp))
+            if !self.expect_next(TokenKind::RPAREN) || !self.expect_next(TokenKind::LBRACE) {
+                return Expression::None
+            }
+            branches.push((cond, self.parse_block()));
 
+            if self.next_token.kind != TokenKind::Else {
+                return Expression::If(branches, None)
             }
-            Token::LBRACKET => {
-                self.next();
-                let ind = self.parse_expression(Precedences::Index);
+            self.next();
+            if self.next_token.kind == TokenKind::If {
                 self.next();
-                Expression::Index(Box::new(exp), Box::new(ind))
+                continue;
             }
-            Token::Plus => {
-                self.next();
-                Expression::Infix(
-                    Infix::Plus,
-                    Box::new(exp),
-                    Box::new(self.parse_expression(Precedences::Sum))
-                )
+            if !self.expect_next(TokenKind::LBRACE) {
+                return Expression::None
             }
-            Token::Minus => {
+            return Expression::If(branches, Some(self.parse_block()))
+        }
+    }
+
+    /// Parses `(a)` as `a`, and `()`, `(a,)` or `(a, b)` as a tuple. Followed
+    /// by `=>`, the names are the parameters of an arrow function.
+    fn parse_group(&mut self) -> Expression {
+        match self.parse_list(TokenKind::RPAREN) {
+            Some((exps, _)) if self.next_token.kind == TokenKind::Arrow && !self.in_guard => {
                 self.next();
-                Expression::Infix(
-                    Infix::Minus,
-                    Box::new(exp),
-                    Box::new(self.parse_expression(Precedences::Sum))
-                )
+                let mut params = Vec::new();
+                for exp in exps {
+                    match exp {
+                        Expression::Ident(ident) => {
+                            params.push(ident);
+                        }
+                        _ => {
+                            self.error_message(self.cur_token.span, "arrow function parameters must be names".to_string());
+                            return Expression::None
+                        }
+                    }
+                }
+                self.parse_arrow_body(params)
             }
-            Token::Star => {
-                self.next();
-                Expression::Infix(
-                    Infix::Star,
-                    Box::new(exp),
-                    Box::new(self.parse_expression(Precedences::Product))
-                )
+            Some((mut exps, false)) if exps.len() == 1 => {
+                exps.pop().unwrap()
             }
-            Token::Slash => {
-                self.next();
-                Expression::Infix(
-                    Infix::Slash,
-                    Box::new(exp),
-                    Box::new(self.parse_expression(Precedences::Product))
-                )
+            Some((exps, _)) => {
+                Expression::Literal(Literals::Tuple(exps))
             }
-            Token::GT => {
-                self.next(); 
-                Expression::Infix(
-                    Infix::GT,
-                    Box::new(exp),
-                    Box::new(self.parse_expression(Precedences::LessGreater))
-                )
+            None => {
+                Expression::None
             }
-            Token::LT => {
-                self.next();
-                Expression::Infix(
-                    Infix::LT,
-                    Box::new(exp),
-                    Box::new(self.parse_expression(Precedences::LessGreater))
-                )
+        }
+    }
+
+    /// Parses `{key: value, ...}`. A trailing comma is allowed.
+    fn parse_hash(&mut self) -> Expression {
+        let entries = self.in_group(|parser| {
+            let mut entries = Vec::new();
+            while parser.next_token.kind != TokenKind::RBRACE {
+                parser.next();
+                let key = parser.parse_expression(Precedences::Lowest);
+                if matches!(key, Expression::None) || !parser.expect_next(TokenKind::Colon) {
+                    return None
+                }
+                parser.next();
+                let val = parser.parse_expression(Precedences::Lowest);
+                if matches!(val, Expression::None) {
+                    return None
+                }
+                entries.push((key, val));
+                if parser.next_token.kind != TokenKind::Comma {
+                    break;
+                }
+                parser.next();
+            }
+            Some(entries)
+        });
+
+        match entries {
+            Some(entries) if self.expect_closing(TokenKind::RBRACE) => {
+                Expression::Literal(Literals::Hash(entries))
             }
             _ => {
                 Expression::None
             }
         }
     }
-    fn parse_function_params(&mut self) -> Vec<Ident> {
-        let mut idents = Vec::new();
-    
+
+    /// Parses what follows the `=>`, either a block or a single expression.
+    fn parse_arrow_body(&mut self, params: Vec<Ident>) -> Expression {
+        let params = params.into_iter().map(Param::new).collect();
+        let loops = std::mem::take(&mut self.loops);
+        // like after an operator, the body may start on the next line
+        self.skip_newlines();
+        let body = if self.next_token.kind == TokenKind::LBRACE {
+            self.next();
+            self.parse_block()
+        } else {
+            self.next();
+            vec![Statement::ExpressionStmt(self.parse_expression(Precedences::Lowest))]
+        };
+        self.loops = loops;
+
+        Expression::Function(params, body)
+    }
+
+    /// Parses `match value { pattern => body ... }`. Arms are separated by
+    /// newlines or commas.
+    fn parse_match(&mut self) -> Expression {
+        self.next();
+        let subject = self.parse_expression(Precedences::Lowest);
+        if matches!(subject, Expression::None) || !self.expect_next(TokenKind::LBRACE) {
+            return Expression::None
+        }
+
+        // like in a block, newlines separate the arms
+        let groups = std::mem::take(&mut self.groups);
+        let arms = self.parse_match_arms();
+        self.groups = groups;
+        self.skip_group_newlines();
+
+        match arms {
+            Some(arms) => {
+                Expression::Match(Box::new(subject), arms)
+            }
+            None => {
+                Expression::None
+            }
+        }
+    }
+
+    fn parse_match_arms(&mut self) -> Option<Vec<MatchArm>> {
+        let mut arms: Vec<MatchArm> = Vec::new();
+        let mut spans: Vec<Span> = Vec::new();
+
         self.next();
-        match &self.cur_token {
-            Token::Ident(i) => {
-                idents.push(Ident { literal: i.to_string() });
+        while self.cur_token.kind != TokenKind::RBRACE {
+            match self.cur_token.kind {
+                TokenKind::NewLine | TokenKind::Comma | TokenKind::DocComment(_) => {
+                    self.next();
+                    continue;
+                }
+                TokenKind::EOF => {
+                    self.error_at_cur(vec![TokenKind::RBRACE.to_string()]);
+                    return None
+                }
+                _ => {}
+            }
+
+            let span = self.cur_token.span;
+            let pattern = self.parse_pattern()?;
+            let mut guard = None;
+            if self.next_token.kind == TokenKind::If {
                 self.next();
+                self.next();
+                self.in_guard = true;
+                let exp = self.parse_expression(Precedences::Lowest);
+                self.in_guard = false;
+                if matches!(exp, Expression::None) {
+                    return None
+                }
+                guard = Some(exp);
+            }
+            if !self.expect_next(TokenKind::Arrow) {
+                return None
+            }
+            self.skip_newlines();
+            let body = if self.next_token.kind == TokenKind::LBRACE {
+                self.next();
+                self.parse_block()
+            } else {
+                self.next();
+                let exp = self.parse_expression(Precedences::Lowest);
+                if matches!(exp, Expression::None) {
+                    return None
+                }
+                vec![Statement::ExpressionStmt(exp)]
+            };
+            if !matches!(self.next_token.kind, TokenKind::NewLine | TokenKind::Comma | TokenKind::DocComment(_) | TokenKind::RBRACE) {
+                self.error_at_next(vec![TokenKind::NewLine.to_string(), TokenKind::Comma.to_string(), TokenKind::RBRACE.to_string()]);
+                return None
+            }
+
+            // an arm is dead if an earlier arm without a guard takes all its values
+            let covering = arms
+                .iter()
+                .zip(&spans)
+                .find(|(arm, _)| arm.guard.is_none() && Self::pattern_covers(&arm.pattern, &pattern));
+            if let Some((_, covering)) = covering {
+                self.warnings.push(Warning {
+                    span,
+                    message: format!("unreachable match arm, the arm at {} already matches every value it does", covering),
+                });
+            }
+
+            arms.push(MatchArm { pattern, guard, body });
+            spans.push(span);
+            self.next();
+        }
+
+        Some(arms)
+    }
+
+    /// Whether every value matched by `other` is also matched by `pattern`.
+    fn pattern_covers(pattern: &Pattern, other: &Pattern) -> bool {
+        match (pattern, other) {
+            (Pattern::Wildcard | Pattern::Ident(_), _) => {
+                true
+            }
+            // compared like the runtime does, so `1.0` covers `1`
+            (Pattern::Literal(l), Pattern::Literal(r)) => {
+                Object::from_literal(l).equals(&Object::from_literal(r))
+            }
+            (Pattern::Range(start, end), Pattern::Literal(Literals::Int(i))) => {
+                start <= i && i < end
+            }
+            (Pattern::Range(start, end), Pattern::Range(other_start, other_end)) => {
+                other_start >= other_end || (start <= other_start && other_end <= end)
+            }
+            (Pattern::Array(patterns, rest), Pattern::Array(others, other_rest)) => {
+                let lengths_covered = match (rest, other_rest) {
+                    (None, None) => patterns.len() == others.len(),
+                    (None, Some(_)) => false,
+                    (Some(_), _) => others.len() >= patterns.len(),
+                };
+                lengths_covered && patterns.iter().zip(others).all(|(p, o)| Self::pattern_covers(p, o))
+            }
+            (Pattern::Hash(entries), Pattern::Hash(others)) => {
+                entries.iter().all(|(key, p)| {
+                    others.iter().any(|(other_key, o)| key == other_key && Self::pattern_covers(p, o))
+                })
             }
             _ => {
-                return idents
+                false
             }
         }
-        if self.cur_token == Token::RPAREN {
-            return idents
+    }
+
+    fn parse_template(&mut self) -> Expression {
+        let mut parts = Vec::new();
+        if let TokenKind::TemplateStart(s) = &self.cur_token.kind {
+            if !s.is_empty() {
+                parts.push(Expression::Literal(Literals::String(s.clone())));
+            }
         }
 
-        while self.cur_token == Token::Comma {
-            match &self.next_token {
-                Token::Ident(i) => {
-                    idents.push(Ident{literal: i.to_string()});
-                    self.next();
-                    self.next();
+        loop {
+            let exp = self.in_group(|parser| {
+                parser.next();
+                parser.parse_expression(Precedences::Lowest)
+            });
+            if matches!(exp, Expression::None) {
+                return Expression::None
+            }
+            parts.push(exp);
+            self.next();
+            match &self.cur_token.kind {
+                TokenKind::TemplateMiddle(s) => {
+                    if !s.is_empty() {
+                        parts.push(Expression::Literal(Literals::String(s.clone())));
+                    }
+                }
+                TokenKind::TemplateEnd(s) => {
+                    if !s.is_empty() {
+                        parts.push(Expression::Literal(Literals::String(s.clone())));
+                    }
+                    break;
+                }
+                _ => {
+                    self.error_at_cur(vec![TokenKind::RBRACE.to_string()]);
+                    return Expression::None
                 }
-                _ => {}
             }
+        }
+
+        Expression::Template(parts)
+    }
 
+    fn parse_infix(&mut self, exp: Expression) -> Expression {
+        return match self.cur_token.kind {
+            TokenKind::LPAREN => {
+                match self.parse_call_args() {
+                    Some(args) => {
+                        Expression::FunctionCall(args, Box::new(exp))
+                    }
+                    None => {
+                        Expression::None
+                    }
+                }
+            }
+            TokenKind::LBRACKET => {
+                self.parse_index(exp)
+            }
+            TokenKind::Dot => {
+                let name = match self.expect_ident() {
+                    Some(name) => name,
+                    None => return Expression::None,
+                };
+                if !self.expect_next(TokenKind::LPAREN) {
+                    return Expression::None
+                }
+                match self.parse_call_args() {
+                    Some(args) => {
+                        Expression::MethodCall(Box::new(exp), name, args)
+                    }
+                    None => {
+                        Expression::None
+                    }
+                }
+            }
+            TokenKind::Pipe => {
+                self.parse_pipe(exp)
+            }
+            _ => {
+                let infix = match Self::token_to_infix(&self.cur_token.kind) {
+                    Some(infix) => infix,
+                    None => return Expression::None,
+                };
+                let precedence = Self::token_to_precedence(&self.cur_token.kind);
+                // an operator at the end of a line continues on the next one
+                self.skip_newlines();
+                self.next();
+                let right = self.parse_expression(precedence);
+                if matches!(right, Expression::None) {
+                    return Expression::None
+                }
+                Expression::Infix(
+                    infix,
+                    Box::new(exp),
+                    Box::new(right)
+                )
+            }
         }
-        idents
-        
     }
 
-    fn parse_expression_list(&mut self) -> Vec<Expression> {
-        let mut args = Vec::new();
-        if self.cur_token != Token::LPAREN {
-            return args;
+    /// Parses `left[i]` or the slice `left[start:end]` from the `[`.
+    fn parse_index(&mut self, left: Expression) -> Expression {
+        let exp = self.in_group(|parser| {
+            let mut start = None;
+            if parser.next_token.kind != TokenKind::Colon {
+                parser.next();
+                let ind = parser.parse_expression(Precedences::Lowest);
+                if matches!(ind, Expression::None) {
+                    return Expression::None
+                }
+                if parser.next_token.kind != TokenKind::Colon {
+                    return Expression::Index(Box::new(left), Box::new(ind))
+                }
+                start = Some(Box::new(ind));
+            }
+
+            parser.next();
+            let mut end = None;
+            if parser.next_token.kind != TokenKind::RBRACKET {
+                parser.next();
+                let ind = parser.parse_expression(Precedences::Lowest);
+                if matches!(ind, Expression::None) {
+                    return Expression::None
+                }
+                end = Some(Box::new(ind));
+            }
+            Expression::Slice(Box::new(left), start, end)
+        });
+
+        if matches!(exp, Expression::None) || !self.expect_next(TokenKind::RBRACKET) {
+            return Expression::None
         }
+        exp
+    }
+
+    /// Parses the right side of `left |> f` or `left |> f(args)`. Both become
+    /// plain calls with `left` as the first argument. The right side only
+    /// takes calls and indexes, so `xs |> len == 2` compares the result.
+    fn parse_pipe(&mut self, left: Expression) -> Expression {
+        self.skip_newlines();
         self.next();
-        args.push(self.parse_expression(Precedences::Lowest));
+        let right = self.parse_expression(Precedences::Prefix);
+        let left = Arg { name: None, value: left };
+        match right {
+            Expression::None => {
+                Expression::None
+            }
+            Expression::FunctionCall(mut args, function) => {
+                args.insert(0, left);
+                Expression::FunctionCall(args, function)
+            }
+            function => {
+                Expression::FunctionCall(vec![left], Box::new(function))
+            }
+        }
+    }
 
-        while self.next_token == Token::Comma {
-            self.next();
-            self.next();
-            args.push(self.parse_expression(Precedences::Lowest));
+    fn token_to_infix(token: &TokenKind) -> Option<Infix> {
+        let infix = match token {
+            TokenKind::Plus => Infix::Plus,
+            TokenKind::Minus => Infix::Minus,
+            TokenKind::Star => Infix::Star,
+            TokenKind::Slash => Infix::Slash,
+            TokenKind::Percent => Infix::Percent,
+            TokenKind::GT => Infix::GT,
+            TokenKind::LT => Infix::LT,
+            TokenKind::GTE => Infix::GTE,
+            TokenKind::LTE => Infix::LTE,
+            TokenKind::EQ => Infix::EQ,
+            TokenKind::NotEQ => Infix::NotEQ,
+            TokenKind::And => Infix::And,
+            TokenKind::Or => Infix::Or,
+            TokenKind::DotDot => Infix::Range,
+            TokenKind::DotDotEq => Infix::RangeInclusive,
+            _ => return None,
+        };
+        Some(infix)
+    }
+
+    /// Parses `(a, b = 2, ...rest)` starting at the `(`. A trailing comma
+    /// is allowed.
+    fn parse_function_params(&mut self) -> Option<Vec<Param>> {
+        let params = self.in_group(|parser| {
+            let mut params: Vec<Param> = Vec::new();
+            while parser.next_token.kind != TokenKind::RPAREN {
+                if params.last().is_some_and(|param| param.rest) {
+                    parser.error_message(parser.next_token.span, "the rest parameter must be the last one".to_string());
+                    return None
+                }
+                let rest = parser.next_token.kind == TokenKind::Ellipsis;
+                if rest {
+                    parser.next();
+                }
+                let mut param = Param::new(parser.expect_ident()?);
+                param.rest = rest;
+                if params.iter().any(|p| p.ident == param.ident) {
+                    parser.error_message(parser.cur_token.span, format!("parameter `{}` is declared twice", param.ident));
+                    return None
+                }
+                if !rest && parser.next_token.kind == TokenKind::Assign {
+                    parser.next();
+                    parser.next();
+                    let default = parser.parse_expression(Precedences::Lowest);
+                    if matches!(default, Expression::None) {
+                        return None
+                    }
+                    param.default = Some(default);
+                }
+                params.push(param);
+                if parser.next_token.kind != TokenKind::Comma {
+                    break;
+                }
+                parser.next();
+            }
+            Some(params)
+        })?;
+
+        if !self.expect_closing(TokenKind::RPAREN) {
+            return None
+        }
+        Some(params)
+    }
+
+    /// Parses call arguments, where `name: value` passes one by name.
+    fn parse_call_args(&mut self) -> Option<Vec<Arg>> {
+        let args = self.in_group(|parser| {
+            let mut args: Vec<Arg> = Vec::new();
+            while parser.next_token.kind != TokenKind::RPAREN {
+                parser.next();
+                let mut name = None;
+                if let TokenKind::Ident(i) = &parser.cur_token.kind {
+                    if parser.next_token.kind == TokenKind::Colon {
+                        name = Some(Ident { literal: i.clone() });
+                        parser.next();
+                        parser.next();
+                    }
+                }
+                if name.is_none() && args.last().is_some_and(|arg| arg.name.is_some()) {
+                    parser.error_message(parser.cur_token.span, "positional arguments must come before named ones".to_string());
+                    return None
+                }
+                let value = parser.parse_expression(Precedences::Lowest);
+                if matches!(value, Expression::None) {
+                    return None
+                }
+                args.push(Arg { name, value });
+                if parser.next_token.kind != TokenKind::Comma {
+                    break;
+                }
+                parser.next();
+            }
+            Some(args)
+        })?;
+
+        if !self.expect_closing(TokenKind::RPAREN) {
+            return None
         }
+        Some(args)
+    }
 
-        args
+    /// Parses comma separated expressions from the current opening token up
+    /// to `end`. A trailing comma is allowed.
+    fn parse_expression_list(&mut self, end: TokenKind) -> Option<Vec<Expression>> {
+        self.parse_list(end).map(|(args, _)| args)
     }
 
-}
+    /// Like `parse_expression_list`, but also tells whether there was a comma.
+    fn parse_list(&mut self, end: TokenKind) -> Option<(Vec<Expression>, bool)> {
+        let mut comma = false;
+        let args = self.in_group(|parser| {
+            let mut args = Vec::new();
+            while parser.next_token.kind != end {
+                parser.next();
+                let exp = parser.parse_expression(Precedences::Lowest);
+                if matches!(exp, Expression::None) {
+                    return None
+                }
+                args.push(exp);
+                if parser.next_token.kind != TokenKind::Comma {
+                    break;
+                }
+                comma = true;
+                parser.next();
+            }
+            Some(args)
+        })?;
 
+        if !self.expect_closing(end) {
+            return None
+        }
+        Some((args, comma))
+    }
 
+    /// Moves to the closing token `end` of a comma separated list.
+    fn expect_closing(&mut self, end: TokenKind) -> bool {
+        if self.next_token.kind != end {
+            self.error_at_next(vec![TokenKind::Comma.to_string(), end.to_string()]);
+            return false
+        }
+        self.next();
+        true
+    }
 
+}
@@ -1,41 +1,188 @@
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
-pub enum Token {
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash, Default)]
+pub struct Span {
+    /// Byte offset of the first character of the token.
+    pub offset: usize,
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column number, starting at 1.
+    pub column: usize,
+    /// Length of the token in bytes.
+    pub len: usize,
+}
+
+impl Span {
+    /// The text of the span in the source it was lexed from.
+    pub fn slice<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.offset..self.offset + self.len]
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, span: Span) -> Self {
+        Token {
+            kind,
+            span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenKind {
     Assign,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
 
 
-    Ident(String),
+    Ident(Rc<str>),
     Int(i64),
+    Float(f64),
     Bool(bool),
     String(String),
+    // pieces of a string with `${...}` interpolations, split around them
+    TemplateStart(String),
+    TemplateMiddle(String),
+    TemplateEnd(String),
 
     Comma,
+    Semicolon,
+    Colon,
+    Arrow,
+    Ellipsis,
+    Dot,
+    DotDot,
+    DotDotEq,
+    Pipe,
 
     Plus,
     Minus,
     Star,
     Slash,
-    EQ, 
+    Percent,
+    Bang,
+    EQ,
     NotEQ,
-    GT, 
+    GT,
     LT,
+    GTE,
+    LTE,
+    And,
+    Or,
 
     LPAREN,
-    RPAREN,   
-    LBRACE,  
-    RBRACE,  
+    RPAREN,
+    LBRACE,
+    RBRACE,
     LBRACKET,
     RBRACKET,
 
-    If, 
+    If,
     Else,
     Func,
     Var,
     Return,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Match,
 
     NewLine,
+    DocComment(String),
+
+    // only produced by Lexer::new_lossless
+    Whitespace(String),
+    Comment(String),
 
     Illegal,
+    Error(String),
     EOF,
 
     None,
 }
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            TokenKind::Ident(i) => return write!(f, "identifier `{}`", i),
+            TokenKind::Int(i) => return write!(f, "integer `{}`", i),
+            TokenKind::Float(fl) => return write!(f, "float `{}`", fl),
+            TokenKind::Bool(b) => return write!(f, "`{}`", b),
+            TokenKind::String(_) | TokenKind::TemplateStart(_) => return write!(f, "string"),
+            TokenKind::TemplateMiddle(_) | TokenKind::TemplateEnd(_) => return write!(f, "`}}`"),
+            TokenKind::NewLine => return write!(f, "newline"),
+            TokenKind::DocComment(_) => return write!(f, "doc comment"),
+            TokenKind::Whitespace(_) => return write!(f, "whitespace"),
+            TokenKind::Comment(_) => return write!(f, "comment"),
+            TokenKind::Illegal => return write!(f, "illegal character"),
+            TokenKind::Error(msg) => return write!(f, "{}", msg),
+            TokenKind::EOF => return write!(f, "end of file"),
+            TokenKind::None => return write!(f, "nothing"),
+            TokenKind::Assign => "=",
+            TokenKind::PlusAssign => "+=",
+            TokenKind::MinusAssign => "-=",
+            TokenKind::StarAssign => "*=",
+            TokenKind::SlashAssign => "/=",
+            TokenKind::PercentAssign => "%=",
+            TokenKind::Comma => ",",
+            TokenKind::Semicolon => ";",
+            TokenKind::Colon => ":",
+            TokenKind::Arrow => "=>",
+            TokenKind::Ellipsis => "...",
+            TokenKind::Dot => ".",
+            TokenKind::DotDot => "..",
+            TokenKind::DotDotEq => "..=",
+            TokenKind::Pipe => "|>",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Star => "*",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::Bang => "!",
+            TokenKind::EQ => "==",
+            TokenKind::NotEQ => "!=",
+            TokenKind::GT => ">",
+            TokenKind::LT => "<",
+            TokenKind::GTE => ">=",
+            TokenKind::LTE => "<=",
+            TokenKind::And => "&&",
+            TokenKind::Or => "||",
+            TokenKind::LPAREN => "(",
+            TokenKind::RPAREN => ")",
+            TokenKind::LBRACE => "{",
+            TokenKind::RBRACE => "}",
+            TokenKind::LBRACKET => "[",
+            TokenKind::RBRACKET => "]",
+            TokenKind::If => "if",
+            TokenKind::Else => "else",
+            TokenKind::Func => "func",
+            TokenKind::Var => "var",
+            TokenKind::Return => "return",
+            TokenKind::While => "while",
+            TokenKind::For => "for",
+            TokenKind::In => "in",
+            TokenKind::Break => "break",
+            TokenKind::Continue => "continue",
+            TokenKind::Match => "match",
+        };
+        write!(f, "`{}`", symbol)
+    }
+}